@@ -68,7 +68,7 @@ impl Index2D {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Coordinate {
     x: isize,
     y: isize,
@@ -1,5 +1,6 @@
 const W_DAY: usize = 10;
 const W_PART: usize = 10;
+const W_VARIANT: usize = 24;
 
 fn mean(numbers: &[f64]) -> f64 {
     numbers.iter().sum::<f64>() / numbers.len() as f64
@@ -25,6 +26,17 @@ pub fn benchmark_run<S: ?Sized, T, F: Fn(&S) -> T>(f: F, input: &S) -> f64 {
     mean(&run_times)
 }
 
+pub fn read_day_input(day_number_string: &str) -> String {
+    let input_path = format!("inputs/{}.in", day_number_string);
+    let error_msg = format!("Unable to open input file {}", &input_path);
+    std::fs::read_to_string(&input_path).expect(error_msg.as_str())
+}
+
+fn format_duration(duration: f64) -> String {
+    let duration_string = format!("{:.3}", duration * 1000.);
+    format!("{} ms", &duration_string[..5])
+}
+
 pub fn print_header() {
     print!("{:<w$}", "day", w = W_DAY);
     print!("{:<w$}", "part 1", w = W_PART);
@@ -36,13 +48,23 @@ pub fn print_header() {
 pub fn print_day(day: u8, p1: f64, p2: f64) {
     print!("{:<w$}", format!("day {:02}", day), w = W_DAY);
 
-    let mut p1_dur = format!("{:.3}", p1 * 1000.).to_string();
-    p1_dur = format!("{} ms", &p1_dur[..5]);
-    print!("{:<w$}", p1_dur, w = W_PART);
+    print!("{:<w$}", format_duration(p1), w = W_PART);
+    println!("{:<w$}", format_duration(p2), w = W_PART);
+}
 
-    let mut p2_dur = format!("{:.3}", p2 * 1000.).to_string();
-    p2_dur = format!("{} ms", &p2_dur[..5]);
-    println!("{:<w$}", p2_dur, w = W_PART);
+pub fn print_variants_header() {
+    println!();
+    print!("{:<w$}", "day", w = W_DAY);
+    print!("{:<w$}", "variant", w = W_VARIANT);
+    print!("{:<w$}", "time", w = W_PART);
+    println!();
+    println!("{:-<w$}", "", w = W_DAY + W_VARIANT + W_PART);
+}
+
+pub fn print_variant(day: u8, variant: &str, duration: f64) {
+    print!("{:<w$}", format!("day {:02}", day), w = W_DAY);
+    print!("{:<w$}", variant, w = W_VARIANT);
+    println!("{:<w$}", format_duration(duration), w = W_PART);
 }
 
 #[macro_export]
@@ -51,9 +73,7 @@ macro_rules! benchmark_all {
         print_header();
         $(
         let day_number_string = &stringify!($day).to_string()[4..];
-        let input_path = format!("inputs/{}.in", day_number_string);
-        let error_msg = format!("Unable to open input file {}", &input_path);
-        let raw_input = std::fs::read_to_string(&input_path).expect(error_msg.as_str());
+        let raw_input = read_day_input(day_number_string);
 
         let p1_duration = benchmark_run($day::part_1, &raw_input);
         let p2_duration = benchmark_run($day::part_2, &raw_input);
@@ -61,4 +81,20 @@ macro_rules! benchmark_all {
         print_day(day_number_string.parse().unwrap(), p1_duration, p2_duration);
         )*
     }};
-}
\ No newline at end of file
+}
+
+/// Benchmarks alternative solvers, given as `day_xx::function` paths, against the day's input.
+#[macro_export]
+macro_rules! benchmark_variants {
+    ($($day:ident :: $variant:ident),*) => {{
+        print_variants_header();
+        $(
+        let day_number_string = &stringify!($day).to_string()[4..];
+        let raw_input = read_day_input(day_number_string);
+
+        let duration = benchmark_run($day::$variant, &raw_input);
+
+        print_variant(day_number_string.parse().unwrap(), stringify!($variant), duration);
+        )*
+    }};
+}
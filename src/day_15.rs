@@ -18,7 +18,7 @@ fn read_input(content: &str) -> io::Result<Vec<Vec<u32>>> {
 }
 // End of block from ChatGPT
 
-fn maze_shortest_path(grid: &Vec<Vec<u32>>) -> u64 {
+fn maze_shortest_path(grid: &[Vec<u32>]) -> u64 {
     let size = grid.len();
 
     let mut pqueue: PairingHeap<[i32; 2], u64> = PairingHeap::new();
@@ -46,7 +46,7 @@ fn maze_shortest_path(grid: &Vec<Vec<u32>>) -> u64 {
     memo[size - 1][size - 1]
 }
 
-fn expand_grid(original_grid: &Vec<Vec<u32>>) -> Vec<Vec<u32>> {
+fn expand_grid(original_grid: &[Vec<u32>]) -> Vec<Vec<u32>> {
     let size = original_grid.len();
     let mut expanded_grid = vec![vec![0; size * 5]; size * 5];
 
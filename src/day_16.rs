@@ -1,53 +1,32 @@
-use std::collections::HashMap;
-use lazy_static::lazy_static;
-
-lazy_static! {
-    static ref HEX_FIELD_BINARY_DICT: HashMap<char, &'static str> = HashMap::from([
-        ('0', "0000"),
-        ('1', "0001"),
-        ('2', "0010"),
-        ('3', "0011"),
-        ('4', "0100"),
-        ('5', "0101"),
-        ('6', "0110"),
-        ('7', "0111"),
-        ('8', "1000"),
-        ('9', "1001"),
-        ('A', "1010"),
-        ('B', "1011"),
-        ('C', "1100"),
-        ('D', "1101"),
-        ('E', "1110"),
-        ('F', "1111"),
-    ]);
-}
+mod encoder;
+mod fold;
+#[cfg(test)]
+mod fuzz;
+#[cfg(test)]
+mod generator;
+mod simplify;
+mod stream;
+mod vm;
 
-fn binary_string_from_hex_string(hex_string: &str) -> String {
-    let mut binary = String::new();
-    for hex_byte in hex_string.chars() {
-        if let Some(bin) = HEX_FIELD_BINARY_DICT.get(&hex_byte) {
-            binary += bin;
-        }
-    }
-    binary
-}
+use fold::PacketFold;
+use stream::{DecodeError, Packets};
+use vm::{Program, StandardOperators};
 
 #[allow(dead_code)]
-#[derive(Debug)]
-enum PacketContents {
+#[derive(Debug, PartialEq, Eq)]
+pub enum PacketContents {
     Number(u128),
     SubPackets(Vec<Packet>),
 }
 
+/// Transmissions may nest packets arbitrarily deep, so packet trees are walked with explicit stacks rather than recursion.
 #[allow(dead_code)]
-#[derive(Debug)]
-struct Packet {
-    binary_string: String,
+#[derive(Debug, PartialEq, Eq)]
+pub struct Packet {
+    length_in_bits: usize,
     version: u128,
     type_id: u128,
     contents: PacketContents,
-    version_sum: u128,
-    value: u128,
 }
 
 impl Packet {
@@ -55,99 +34,85 @@ impl Packet {
     #[allow(dead_code)]
     fn new() -> Packet {
         Packet {
-            binary_string: "".to_string(),
+            length_in_bits: 0,
             version: 0,
             type_id: 0,
             contents: PacketContents::Number(0),
-            version_sum: 0,
-            value: 0,
         }
     }
 
-    fn from_hex_string(hex_string: &str) -> Packet {
-        let binary_string = binary_string_from_hex_string(hex_string);
-        Self::from_binary_string(&binary_string)
-    }
-
-    fn from_binary_string(binary_string: &str) -> Packet {
-
-        let version = u128::from_str_radix(&binary_string[..=2], 2).unwrap();
-        let type_id = u128::from_str_radix(&binary_string[3..=5], 2).unwrap();
-        let mut version_sum = version;
-        match type_id {
-
-            // Literal packet
-            4 => {
-                let mut literal = String::new();
-                let mut last = false;
-                let mut last_idx = 0;
-                for bit_group_start_index in (6..binary_string.len()).step_by(5) {
-                    for (i, bit) in binary_string[bit_group_start_index..(bit_group_start_index + 5)].chars().enumerate() {
-                        if i == 0 && bit == '0' {
-                            last = true;
-                            last_idx = bit_group_start_index + 5;
-                        } else if i > 0 {
-                            literal.push(bit);
-                        }
-                    }
-                    if last { break }
-                }
-                let number = u128::from_str_radix(&literal, 2).unwrap();
-                Packet {
-                    binary_string: binary_string[..last_idx].to_string(),
-                    version,
-                    type_id,
-                    contents: PacketContents::Number(number),
-                    version_sum,
-                    value: number,
-                }
-            },
-
-            // Operator packet
-            operator_type_id => {
-                let length_type_id = u128::from_str_radix(&binary_string[6..7], 2).unwrap();
-                let mut last_bit = 0;
-                let mut subpackets = Vec::new();
-                match length_type_id {
-                    0 => {
-                        let total_length_in_bits = u128::from_str_radix(&binary_string[7..22], 2).unwrap();
-                        let mut length_in_bits = 0;
-                        while length_in_bits < total_length_in_bits {
-                            let subpacket = Packet::from_binary_string(&binary_string[((22 + length_in_bits) as usize)..]);
-                            length_in_bits += subpacket.binary_string.len() as u128;
-                            version_sum += subpacket.version_sum;
-                            subpackets.push(subpacket);
-                        }
-                        last_bit = 22 + total_length_in_bits
-                    },
-                    1 => {
-                        let num_subpackets = u128::from_str_radix(&binary_string[7..18], 2).unwrap();
-                        let mut length_in_bits = 0;
-                        while (subpackets.len() as u128) < num_subpackets {
-                            let subpacket = Packet::from_binary_string(&binary_string[((18 + length_in_bits) as usize)..]);
-                            length_in_bits += subpacket.binary_string.len() as u128;
-                            version_sum += subpacket.version_sum;
-                            subpackets.push(subpacket);
-                        }
-                        last_bit = 18 + length_in_bits
-                    },
-                    _ => {},
-                }
-                let value = Self::get_operator_value(&subpackets, operator_type_id);
-                Packet {
-                    binary_string: binary_string[..(last_bit as usize)].to_string(),
-                    version,
-                    type_id,
-                    contents: PacketContents::SubPackets(subpackets),
-                    version_sum,
-                    value
-                }
-            },
+    #[allow(dead_code)]
+    pub fn literal(version: u128, value: u128) -> Packet {
+        Packet {
+            length_in_bits: encoder::literal_length(value),
+            version,
+            type_id: 4,
+            contents: PacketContents::Number(value),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn operator(version: u128, type_id: u128, subpackets: Vec<Packet>) -> Packet {
+        assert_ne!(type_id, 4, "Type ID 4 is reserved for literal packets");
+        let subpackets_length_in_bits = subpackets.iter().map(|subpacket| subpacket.length_in_bits).sum();
+        Packet {
+            length_in_bits: encoder::operator_length(subpackets.len(), subpackets_length_in_bits),
+            version,
+            type_id,
+            contents: PacketContents::SubPackets(subpackets),
+        }
+    }
+
+    pub fn version(&self) -> u128 {
+        self.version
+    }
+
+    pub fn type_id(&self) -> u128 {
+        self.type_id
+    }
+
+    /// Folds the sub-packets of each packet before the packet itself.
+    pub fn fold<F: PacketFold>(&self, folder: &mut F) -> F::Output {
+        // Packets still to finish, with the index of the next sub-packet to fold
+        let mut pending: Vec<(&Packet, usize)> = vec![(self, 0)];
+        let mut folded: Vec<F::Output> = Vec::new();
+        while let Some((packet, next_subpacket)) = pending.pop() {
+            match &packet.contents {
+                PacketContents::Number(number) => folded.push(folder.literal(packet, *number)),
+                PacketContents::SubPackets(subpackets) if next_subpacket < subpackets.len() => {
+                    pending.push((packet, next_subpacket + 1));
+                    pending.push((&subpackets[next_subpacket], 0));
+                },
+                PacketContents::SubPackets(subpackets) => {
+                    let folded_subpackets = folded.split_off(folded.len() - subpackets.len());
+                    folded.push(folder.operator(packet, folded_subpackets));
+                },
+            }
         }
+        folded.pop().unwrap()
+    }
+
+    pub fn version_sum(&self) -> u128 {
+        self.fold(&mut fold::VersionSum)
+    }
+
+    pub fn value(&self) -> u128 {
+        self.fold(&mut fold::Value)
     }
 
-    fn get_operator_value(subpackets: &Vec<Packet>, operator_type_id: u128) -> u128 {
-        let mut subpackets_value_iterator = subpackets.iter().map(|subpacket| subpacket.value);
+    /// Value of the packet, or `None` if it overflows or an operator has the wrong number of sub-packets.
+    #[allow(dead_code)]
+    pub fn checked_value(&self) -> Option<u128> {
+        self.fold(&mut fold::CheckedValue)
+    }
+
+    /// Decodes the first packet of a hex transmission, see `stream::Packets`.
+    pub fn from_hex_string(hex_string: &str) -> Result<Packet, DecodeError> {
+        Packets::from_reader(hex_string.as_bytes()).next().unwrap_or(Err(DecodeError::UnexpectedEnd { bit_offset: 0 }))
+    }
+
+    fn get_operator_value(subpacket_values: &[u128], operator_type_id: u128) -> u128 {
+        let mut subpackets_value_iterator = subpacket_values.iter().copied();
         match operator_type_id {
             0 => { subpackets_value_iterator.sum() },
             1 => { subpackets_value_iterator.product() },
@@ -161,42 +126,150 @@ impl Packet {
     }
 }
 
+/// Drops the sub-packets one at a time instead of recursively.
+impl Drop for Packet {
+    fn drop(&mut self) {
+        let mut subpackets = match &mut self.contents {
+            PacketContents::SubPackets(subpackets) if !subpackets.is_empty() => std::mem::take(subpackets),
+            _ => return,
+        };
+        while let Some(mut subpacket) = subpackets.pop() {
+            if let PacketContents::SubPackets(nested) = &mut subpacket.contents {
+                subpackets.append(nested);
+            }
+        }
+    }
+}
+
 
 // fn main() {
 //     let input = fs::read_to_string("../aoc16/inputs.txt").expect("Cannot read inputs.txt");
-//     let packet = Packet::from_hex_string(&input);
-//     println!("{:?}", packet.version_sum);
-//     println!("{:?}", packet.value);
+//     let packet = Packet::from_hex_string(input);
+//     println!("{:?}", packet.version_sum());
+//     println!("{:?}", packet.value());
 // }
 
 pub fn part_1(input: &str) -> u128 {
-    let packet = Packet::from_hex_string(&input);
-    packet.version_sum
+    let packet = Packet::from_hex_string(input).unwrap();
+    packet.version_sum()
 }
 
 pub fn part_2(input: &str) -> u128 {
-    let packet = Packet::from_hex_string(&input);
-    packet.value
+    let packet = Packet::from_hex_string(input).unwrap();
+    packet.value()
+}
+
+/// A transmission decoded and compiled once, so that evaluating it can be benchmarked on its own.
+pub struct CompiledTransmission {
+    packet: Packet,
+    program: Program,
+}
+
+pub fn compile_transmission(input: &str) -> CompiledTransmission {
+    let packet = Packet::from_hex_string(input).unwrap();
+    let program = Program::compile(&packet);
+    CompiledTransmission { packet, program }
+}
+
+pub fn part_2_tree(transmission: &CompiledTransmission) -> u128 {
+    transmission.packet.value()
+}
+
+pub fn part_2_vm(transmission: &CompiledTransmission) -> u128 {
+    transmission.program.run(&StandardOperators)
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::Packet;
+    use super::generator::PacketGenerator;
+    use super::simplify::simplify;
+    use super::stream::Packets;
+    use super::vm::{Program, StandardOperators};
+    use crate::rng::XorShift64;
 
     #[test]
     fn version_sums() {
-        let packet = Packet::from_hex_string("8A004A801A8002F478");
+        let packet = Packet::from_hex_string("8A004A801A8002F478").unwrap();
         println!("\n\n\n{:?}", packet);
-        assert_eq!(packet.version_sum, 16);
+        assert_eq!(packet.version_sum(), 16);
+
+        let packet = Packet::from_hex_string("620080001611562C8802118E34").unwrap();
+        assert_eq!(packet.version_sum(), 12);
+
+        let packet = Packet::from_hex_string("C0015000016115A2E0802F182340").unwrap();
+        assert_eq!(packet.version_sum(), 23);
+
+        let packet = Packet::from_hex_string("A0016C880162017C3686B18A3D4780").unwrap();
+        assert_eq!(packet.version_sum(), 31);
+    }
+
+    #[test]
+    fn vm_and_tree_agree() {
+        let transmission = super::compile_transmission("9C0141080250320F1802104A08");
+        assert_eq!(super::part_2_tree(&transmission), 1);
+        assert_eq!(super::part_2_vm(&transmission), 1);
+    }
 
-        let packet = Packet::from_hex_string("620080001611562C8802118E34");
-        assert_eq!(packet.version_sum, 12);
+    #[test]
+    fn random_packets_round_trip() {
+        let mut rng = XorShift64::from_seed(2021);
+        let generator = PacketGenerator { max_depth: 6, max_subpackets: 4, max_literal: u128::MAX };
+        for _ in 0..1_000 {
+            let packet = generator.packet(&mut rng);
+            let hex_string = packet.to_hex_string();
+
+            let decoded = Packet::from_hex_string(&hex_string).unwrap();
+            assert_eq!(decoded, packet);
+            assert_eq!(decoded.version_sum(), packet.version_sum());
+            assert_eq!(decoded.checked_value(), packet.checked_value());
+
+            let streamed = Packets::from_reader(hex_string.as_bytes()).next().unwrap().unwrap();
+            assert_eq!(streamed, packet);
+        }
+    }
+
+    #[test]
+    fn random_values_round_trip() {
+        // Small literals keep sums and products from overflowing, so `value` itself can be compared
+        let mut rng = XorShift64::from_seed(1612);
+        let generator = PacketGenerator { max_depth: 4, max_subpackets: 3, max_literal: 15 };
+        for _ in 0..1_000 {
+            let packet = generator.packet(&mut rng);
+            let decoded = Packet::from_hex_string(&packet.to_hex_string()).unwrap();
+            assert_eq!(decoded.value(), packet.value());
+        }
+    }
+
+    #[test]
+    fn length_in_bits_round_trip() {
+        // More sub-packets than fit into the 11 bit count field
+        let packet = Packet::operator(1, 0, (0..2_100).map(|i| Packet::literal(i % 8, i % 16)).collect());
+        let decoded = Packet::from_hex_string(&packet.to_hex_string()).unwrap();
+        assert_eq!(decoded, packet);
+        assert_eq!(decoded.value(), (0..2_100).map(|i| i % 16).sum());
+    }
 
-        let packet = Packet::from_hex_string("C0015000016115A2E0802F182340");
-        assert_eq!(packet.version_sum, 23);
+    #[test]
+    fn deep_packets_do_not_overflow_the_stack() {
+        // Version 1 sums with a single counted sub-packet each, around a version 1 literal 7
+        let mut binary = "001000100000000001".repeat(50_000) + "00110000111";
+        while !binary.len().is_multiple_of(4) {
+            binary.push('0');
+        }
+        let hex_string: String = binary.as_bytes()
+            .chunks(4)
+            .map(|nibble| char::from_digit(u32::from_str_radix(std::str::from_utf8(nibble).unwrap(), 2).unwrap(), 16).unwrap())
+            .collect();
 
-        let packet = Packet::from_hex_string("A0016C880162017C3686B18A3D4780");
-        assert_eq!(packet.version_sum, 31);
+        let packet = Packets::from_reader(hex_string.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(packet.version_sum(), 50_001);
+        assert_eq!(packet.value(), 7);
+        assert_eq!(packet.checked_value(), Some(7));
+        assert_eq!(Program::compile(&packet).run(&StandardOperators), 7);
+
+        // Every sum has a single operand, so only the literal is left
+        assert_eq!(simplify(packet), Packet::literal(1, 7));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,120 @@
+use std::cmp::max;
+
+use super::fold::PacketFold;
+use super::{Packet, PacketContents};
+
+const HEADER_BITS: usize = 6;
+const LITERAL_GROUP_BITS: usize = 5;
+const MAX_COUNTED_SUBPACKETS: usize = (1 << 11) - 1;
+const MAX_TOTAL_LENGTH_IN_BITS: usize = (1 << 15) - 1;
+
+fn literal_groups(value: u128) -> usize {
+    max(1, (128 - value.leading_zeros() as usize).div_ceil(4))
+}
+
+pub fn literal_length(value: u128) -> usize {
+    HEADER_BITS + LITERAL_GROUP_BITS * literal_groups(value)
+}
+
+/// Length of an operator packet, preferring the shorter sub-packet count over the total length in bits.
+pub fn operator_length(num_subpackets: usize, subpackets_length_in_bits: usize) -> usize {
+    let length_field_bits = if num_subpackets <= MAX_COUNTED_SUBPACKETS { 11 } else { 15 };
+    HEADER_BITS + 1 + length_field_bits + subpackets_length_in_bits
+}
+
+/// Length of the shortest encoding of a packet, regardless of how it was originally encoded.
+pub struct EncodedLength;
+
+impl PacketFold for EncodedLength {
+    type Output = usize;
+
+    fn literal(&mut self, _packet: &Packet, value: u128) -> usize {
+        literal_length(value)
+    }
+
+    fn operator(&mut self, _packet: &Packet, subpackets: Vec<usize>) -> usize {
+        operator_length(subpackets.len(), subpackets.iter().sum())
+    }
+}
+
+fn push_bits(binary: &mut String, value: u128, n: usize) {
+    for i in (0..n).rev() {
+        binary.push(if (value >> i) & 1 == 1 { '1' } else { '0' });
+    }
+}
+
+#[allow(dead_code)]
+impl Packet {
+    pub fn to_binary_string(&self) -> String {
+        let mut binary = String::new();
+        self.encode_into(&mut binary);
+        binary
+    }
+
+    /// Shortest hex transmission decoding back to this packet, padded with zero bits to a whole hex digit.
+    pub fn to_hex_string(&self) -> String {
+        let binary = self.to_binary_string();
+        binary.as_bytes()
+            .chunks(4)
+            .map(|nibble| {
+                let digit = nibble.iter().enumerate().fold(0, |digit, (i, bit)| digit | (((*bit == b'1') as u32) << (3 - i)));
+                char::from_digit(digit, 16).unwrap().to_ascii_uppercase()
+            })
+            .collect()
+    }
+
+    /// Writes out each packet before its sub-packets.
+    fn encode_into(&self, binary: &mut String) {
+        let mut pending = vec![self];
+        while let Some(packet) = pending.pop() {
+            push_bits(binary, packet.version, 3);
+            push_bits(binary, packet.type_id, 3);
+            match &packet.contents {
+                PacketContents::Number(value) => {
+                    for group in (0..literal_groups(*value)).rev() {
+                        push_bits(binary, (group > 0) as u128, 1);
+                        push_bits(binary, (value >> (4 * group)) & 0b1111, 4);
+                    }
+                },
+                PacketContents::SubPackets(subpackets) => {
+                    if subpackets.len() <= MAX_COUNTED_SUBPACKETS {
+                        push_bits(binary, 1, 1);
+                        push_bits(binary, subpackets.len() as u128, 11);
+                    } else {
+                        let subpackets_length_in_bits: usize = subpackets.iter().map(|subpacket| subpacket.fold(&mut EncodedLength)).sum();
+                        assert!(subpackets_length_in_bits <= MAX_TOTAL_LENGTH_IN_BITS, "Operator packet too large to encode");
+                        push_bits(binary, 0, 1);
+                        push_bits(binary, subpackets_length_in_bits as u128, 15);
+                    }
+                    pending.extend(subpackets.iter().rev());
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_encoding() {
+        assert_eq!(Packet::literal(6, 2021).to_binary_string(), "110100101111111000101");
+        assert_eq!(Packet::literal(6, 2021).to_hex_string(), "D2FE28");
+        assert_eq!(Packet::literal(0, 0).to_binary_string(), "00010000000");
+    }
+
+    #[test]
+    fn re_encoding_uses_subpacket_count() {
+        // The original uses a total length in bits, the re-encoding counts the two literals instead
+        let packet = Packet::from_hex_string("38006F45291200").unwrap();
+        let encoded = packet.to_binary_string();
+        assert_eq!(encoded.len(), packet.fold(&mut EncodedLength));
+        assert_eq!(encoded.len(), packet.length_in_bits - 4);
+
+        let decoded = Packet::from_hex_string(&packet.to_hex_string()).unwrap();
+        assert_eq!(decoded.value(), packet.value());
+        assert_eq!(decoded.version_sum(), packet.version_sum());
+        assert_eq!(decoded.length_in_bits, encoded.len());
+    }
+}
@@ -0,0 +1,154 @@
+use std::cmp::max;
+
+use super::Packet;
+
+/// Bottom-up aggregation over a packet tree, see `Packet::fold`.
+/// Sub-packets are always folded before the operator packet containing them.
+pub trait PacketFold {
+    type Output;
+
+    fn literal(&mut self, packet: &Packet, value: u128) -> Self::Output;
+
+    fn operator(&mut self, packet: &Packet, subpackets: Vec<Self::Output>) -> Self::Output;
+}
+
+pub struct VersionSum;
+
+impl PacketFold for VersionSum {
+    type Output = u128;
+
+    fn literal(&mut self, packet: &Packet, _value: u128) -> u128 {
+        packet.version()
+    }
+
+    fn operator(&mut self, packet: &Packet, subpackets: Vec<u128>) -> u128 {
+        packet.version() + subpackets.iter().sum::<u128>()
+    }
+}
+
+pub struct Value;
+
+impl PacketFold for Value {
+    type Output = u128;
+
+    fn literal(&mut self, _packet: &Packet, value: u128) -> u128 {
+        value
+    }
+
+    fn operator(&mut self, packet: &Packet, subpackets: Vec<u128>) -> u128 {
+        Packet::get_operator_value(&subpackets, packet.type_id())
+    }
+}
+
+/// Like `Value`, but yields `None` instead of overflowing or panicking on malformed operators.
+pub struct CheckedValue;
+
+impl PacketFold for CheckedValue {
+    type Output = Option<u128>;
+
+    fn literal(&mut self, _packet: &Packet, value: u128) -> Option<u128> {
+        Some(value)
+    }
+
+    fn operator(&mut self, packet: &Packet, subpackets: Vec<Option<u128>>) -> Option<u128> {
+        let values: Vec<u128> = subpackets.into_iter().collect::<Option<_>>()?;
+        match packet.type_id() {
+            0 => values.iter().try_fold(0u128, |sum, value| sum.checked_add(*value)),
+            1 => values.iter().try_fold(1u128, |product, value| product.checked_mul(*value)),
+            2 | 3 if !values.is_empty() => Some(Packet::get_operator_value(&values, packet.type_id())),
+            5..=7 if values.len() == 2 => Some(Packet::get_operator_value(&values, packet.type_id())),
+            _ => None,
+        }
+    }
+}
+
+/// Number of packets on the longest path from the folded packet down to a literal.
+#[allow(dead_code)]
+pub struct MaxDepth;
+
+impl PacketFold for MaxDepth {
+    type Output = usize;
+
+    fn literal(&mut self, _packet: &Packet, _value: u128) -> usize {
+        1
+    }
+
+    fn operator(&mut self, _packet: &Packet, subpackets: Vec<usize>) -> usize {
+        subpackets.into_iter().fold(0, max) + 1
+    }
+}
+
+#[allow(dead_code)]
+pub struct LiteralCount;
+
+impl PacketFold for LiteralCount {
+    type Output = usize;
+
+    fn literal(&mut self, _packet: &Packet, _value: u128) -> usize {
+        1
+    }
+
+    fn operator(&mut self, _packet: &Packet, subpackets: Vec<usize>) -> usize {
+        subpackets.iter().sum()
+    }
+}
+
+/// Counts packets per type ID, accumulated in the folder itself.
+#[allow(dead_code)]
+#[derive(Default)]
+pub struct TypeHistogram {
+    counts: [usize; 8],
+}
+
+#[allow(dead_code)]
+impl TypeHistogram {
+    pub fn counts(&self) -> &[usize; 8] {
+        &self.counts
+    }
+}
+
+impl PacketFold for TypeHistogram {
+    type Output = ();
+
+    fn literal(&mut self, packet: &Packet, _value: u128) {
+        self.counts[packet.type_id() as usize] += 1;
+    }
+
+    fn operator(&mut self, packet: &Packet, _subpackets: Vec<()>) {
+        self.counts[packet.type_id() as usize] += 1;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values() {
+        assert_eq!(Packet::from_hex_string("C200B40A82").unwrap().fold(&mut Value), 3);
+        assert_eq!(Packet::from_hex_string("04005AC33890").unwrap().fold(&mut Value), 54);
+        assert_eq!(Packet::from_hex_string("880086C3E88112").unwrap().fold(&mut Value), 7);
+        assert_eq!(Packet::from_hex_string("CE00C43D881120").unwrap().fold(&mut Value), 9);
+        assert_eq!(Packet::from_hex_string("D8005AC2A8F0").unwrap().fold(&mut Value), 1);
+        assert_eq!(Packet::from_hex_string("F600BC2D8F").unwrap().fold(&mut Value), 0);
+        assert_eq!(Packet::from_hex_string("9C005AC2F8F0").unwrap().fold(&mut Value), 0);
+        assert_eq!(Packet::from_hex_string("9C0141080250320F1802104A08").unwrap().fold(&mut Value), 1);
+    }
+
+    #[test]
+    fn structural_aggregations() {
+        // Three nested operator packets around a single literal
+        let packet = Packet::from_hex_string("8A004A801A8002F478").unwrap();
+        assert_eq!(packet.fold(&mut MaxDepth), 4);
+        assert_eq!(packet.fold(&mut LiteralCount), 1);
+
+        let packet = Packet::from_hex_string("A0016C880162017C3686B18A3D4780").unwrap();
+        assert_eq!(packet.fold(&mut MaxDepth), 4);
+        assert_eq!(packet.fold(&mut LiteralCount), 5);
+
+        let mut histogram = TypeHistogram::default();
+        packet.fold(&mut histogram);
+        assert_eq!(histogram.counts(), &[3, 0, 0, 0, 5, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,107 @@
+use super::encoder::EncodedLength;
+use super::stream::Packets;
+use super::Packet;
+
+const MAX_PACKETS: usize = 64;
+
+/// Property the tests below check against random inputs: decoding arbitrary bytes must never panic or hang,
+/// and everything that does decode has to survive being re-encoded.
+pub fn fuzz_target(data: &[u8]) {
+    if let Err(error) = Packet::from_hex_string(&String::from_utf8_lossy(data)) {
+        _ = error.to_string();
+    }
+
+    for packet in Packets::from_reader(data).take(MAX_PACKETS) {
+        let packet = match packet {
+            Ok(packet) => packet,
+            Err(error) => {
+                _ = error.to_string();
+                return
+            },
+        };
+        let version_sum = packet.version_sum();
+        let value = packet.checked_value();
+
+        let encoded = packet.to_hex_string();
+        assert_eq!(encoded.len() * 4, packet.fold(&mut EncodedLength).next_multiple_of(4));
+        let decoded = Packet::from_hex_string(&encoded).unwrap();
+        assert_eq!(decoded.version_sum(), version_sum);
+        assert_eq!(decoded.checked_value(), value);
+        assert_eq!(decoded.to_hex_string(), encoded);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use super::*;
+    use crate::day_16::generator::PacketGenerator;
+    use crate::rng::XorShift64;
+
+    /// Runs the fuzz target on `num_cases` inputs drawn by `input`, naming the input that made it panic.
+    fn fuzz(seed: u64, num_cases: usize, mut input: impl FnMut(&mut XorShift64) -> Vec<u8>) {
+        let mut rng = XorShift64::from_seed(seed);
+        for case in 0..num_cases {
+            let data = input(&mut rng);
+            if catch_unwind(AssertUnwindSafe(|| fuzz_target(&data))).is_err() {
+                panic!("case {} of seed {} failed on {:?}", case, seed, String::from_utf8_lossy(&data));
+            }
+        }
+    }
+
+    #[test]
+    fn arbitrary_bytes() {
+        fuzz(30, 2_000, |rng| (0..rng.below(64)).map(|_| rng.next_u64() as u8).collect());
+        fuzz(35, 200, |rng| (0..rng.below(4_096)).map(|_| rng.next_u64() as u8).collect());
+    }
+
+    #[test]
+    fn arbitrary_hex_digits() {
+        fuzz(31, 2_000, |rng| (0..rng.below(256)).map(|_| b"0123456789ABCDEF\n"[rng.below(17)]).collect());
+        fuzz(36, 200, |rng| (0..rng.below(4_096)).map(|_| b"0123456789ABCDEF\n"[rng.below(17)]).collect());
+    }
+
+    #[test]
+    fn short_hex_strings() {
+        assert!(Packet::from_hex_string("").is_err());
+        assert!(Packet::from_hex_string("D2").is_err());
+        for data in ["", "0", "D2", "D2FE", "38006F45291200", " \n"] {
+            fuzz_target(data.as_bytes());
+        }
+    }
+
+    #[test]
+    fn long_transmissions() {
+        let generator = PacketGenerator { max_depth: 3, max_subpackets: 12, max_literal: u128::MAX };
+        fuzz(33, 20, |rng| {
+            let transmissions: Vec<String> = (0..8).map(|_| generator.packet(rng).to_hex_string()).collect();
+            transmissions.join("\n").into_bytes()
+        });
+    }
+
+    #[test]
+    fn deep_transmissions() {
+        let generator = PacketGenerator { max_depth: 2, max_subpackets: 2, max_literal: 1_000 };
+        fuzz(34, 2, |rng| {
+            let mut data = generator.chain(rng, 20_000).to_hex_string().into_bytes();
+            data.truncate(data.len() - rng.below(data.len() / 2 + 1));
+            data
+        });
+    }
+
+    #[test]
+    fn mutated_transmissions() {
+        let generator = PacketGenerator { max_depth: 5, max_subpackets: 4, max_literal: u128::MAX };
+        fuzz(32, 1_000, |rng| {
+            let mut data = generator.packet(rng).to_hex_string().into_bytes();
+            for _ in 0..=rng.below(3) {
+                let i = rng.below(data.len());
+                data[i] = b"0123456789ABCDEF"[rng.below(16)];
+            }
+            data.truncate(data.len() - rng.below(data.len() / 2 + 1));
+            data
+        });
+    }
+}
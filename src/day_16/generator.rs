@@ -0,0 +1,55 @@
+use crate::rng::XorShift64;
+
+use super::Packet;
+
+const OPERATOR_TYPE_IDS: [u128; 7] = [0, 1, 2, 3, 5, 6, 7];
+
+/// Generates random well-formed packet trees: comparisons get exactly two sub-packets
+/// and every other operator at least one.
+pub struct PacketGenerator {
+    pub max_depth: usize,
+    pub max_subpackets: usize,
+    pub max_literal: u128,
+}
+
+impl PacketGenerator {
+    pub fn packet(&self, rng: &mut XorShift64) -> Packet {
+        self.packet_with_depth(rng, self.max_depth)
+    }
+
+    /// A chain of `depth` nested operator packets, each with a random packet next to the nested one.
+    /// Built bottom-up without recursion, so that the chain can be as deep as needed.
+    pub fn chain(&self, rng: &mut XorShift64, depth: usize) -> Packet {
+        let mut packet = self.packet(rng);
+        for _ in 0..depth {
+            let version = rng.below(8) as u128;
+            let type_id = OPERATOR_TYPE_IDS[rng.below(OPERATOR_TYPE_IDS.len())];
+            let sibling = self.packet(rng);
+            let subpackets = if rng.chance(1, 2) { vec![packet, sibling] } else { vec![sibling, packet] };
+            packet = Packet::operator(version, type_id, subpackets);
+        }
+        packet
+    }
+
+    fn packet_with_depth(&self, rng: &mut XorShift64, depth: usize) -> Packet {
+        let version = rng.below(8) as u128;
+        if depth == 0 || rng.chance(1, 3) {
+            return Packet::literal(version, self.literal(rng))
+        }
+        let type_id = OPERATOR_TYPE_IDS[rng.below(OPERATOR_TYPE_IDS.len())];
+        let num_subpackets = if type_id >= 5 { 2 } else { 1 + rng.below(self.max_subpackets) };
+        let subpackets = (0..num_subpackets).map(|_| self.packet_with_depth(rng, depth - 1)).collect();
+        Packet::operator(version, type_id, subpackets)
+    }
+
+    fn literal(&self, rng: &mut XorShift64) -> u128 {
+        // Spread values over all bit widths instead of almost always hitting the widest ones
+        let bits = 128 - self.max_literal.leading_zeros();
+        let width = rng.below(bits as usize + 1);
+        if width == 0 {
+            return 0
+        }
+        let value = ((rng.next_u64() as u128) << 64 | rng.next_u64() as u128) >> (128 - width);
+        value.min(self.max_literal)
+    }
+}
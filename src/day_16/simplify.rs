@@ -0,0 +1,161 @@
+use super::{Packet, PacketContents};
+
+const SUM: u128 = 0;
+const PRODUCT: u128 = 1;
+const MINIMUM: u128 = 2;
+const MAXIMUM: u128 = 3;
+
+/// Rewrites a packet tree bottom-up into an equivalent one with the same value and a shorter encoding.
+/// Nested sums and products are flattened, sums drop `+ 0` and products `* 1` operands, single operand
+/// sums, products, minima and maxima are replaced by their operand and comparisons of literals are evaluated.
+/// Versions of packets that get merged away are lost, so the version sum is not preserved.
+#[allow(dead_code)]
+pub fn simplify(packet: Packet) -> Packet {
+    // Operators with sub-packets left to simplify, innermost last
+    let mut open: Vec<OpenOperator> = Vec::new();
+    let mut simplified = open_or_simplify(packet, &mut open);
+    loop {
+        if let Some(packet) = simplified.take() {
+            match open.last_mut() {
+                Some(parent) => parent.simplified.push(packet),
+                None => return packet,
+            }
+        }
+        simplified = match open.last_mut().unwrap().pending.next() {
+            Some(subpacket) => open_or_simplify(subpacket, &mut open),
+            None => Some(open.pop().unwrap().close()),
+        };
+    }
+}
+
+struct OpenOperator {
+    version: u128,
+    type_id: u128,
+    pending: std::vec::IntoIter<Packet>,
+    simplified: Vec<Packet>,
+}
+
+impl OpenOperator {
+    fn close(self) -> Packet {
+        match self.type_id {
+            SUM | PRODUCT | MINIMUM | MAXIMUM => simplify_associative(self.version, self.type_id, self.simplified),
+            _ => simplify_comparison(self.version, self.type_id, self.simplified),
+        }
+    }
+}
+
+/// Literals are simplified right away, operators are opened to simplify their sub-packets first.
+fn open_or_simplify(mut packet: Packet, open: &mut Vec<OpenOperator>) -> Option<Packet> {
+    match std::mem::replace(&mut packet.contents, PacketContents::SubPackets(Vec::new())) {
+        PacketContents::Number(value) => Some(Packet::literal(packet.version, value)),
+        PacketContents::SubPackets(subpackets) => {
+            open.push(OpenOperator {
+                version: packet.version,
+                type_id: packet.type_id,
+                simplified: Vec::with_capacity(subpackets.len()),
+                pending: subpackets.into_iter(),
+            });
+            None
+        },
+    }
+}
+
+fn literal_value(packet: &Packet) -> Option<u128> {
+    match packet.contents {
+        PacketContents::Number(value) => Some(value),
+        PacketContents::SubPackets(_) => None,
+    }
+}
+
+fn simplify_associative(version: u128, type_id: u128, subpackets: Vec<Packet>) -> Packet {
+    let identity = match type_id {
+        SUM => Some(0),
+        PRODUCT => Some(1),
+        _ => None,
+    };
+
+    let mut operands = Vec::with_capacity(subpackets.len());
+    for mut subpacket in subpackets {
+        match &mut subpacket.contents {
+            // Already simplified, so nested operands are flat as well
+            PacketContents::SubPackets(nested) if identity.is_some() && subpacket.type_id == type_id => operands.append(nested),
+            _ => operands.push(subpacket),
+        }
+    }
+
+    if let Some(identity) = identity {
+        let num_operands = operands.len();
+        operands.retain(|operand| literal_value(operand) != Some(identity));
+        if operands.is_empty() && num_operands > 0 {
+            return Packet::literal(version, identity)
+        }
+    }
+    if operands.len() == 1 {
+        return operands.pop().unwrap()
+    }
+    Packet::operator(version, type_id, operands)
+}
+
+fn simplify_comparison(version: u128, type_id: u128, subpackets: Vec<Packet>) -> Packet {
+    let literal_values: Vec<u128> = subpackets.iter().filter_map(literal_value).collect();
+    if subpackets.len() == 2 && literal_values.len() == 2 {
+        Packet::literal(version, Packet::get_operator_value(&literal_values, type_id))
+    } else {
+        Packet::operator(version, type_id, subpackets)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_16::encoder::EncodedLength;
+    use crate::day_16::generator::PacketGenerator;
+    use crate::rng::XorShift64;
+
+    #[test]
+    fn rewrite_rules() {
+        // 3 > (1 + 1)
+        let x = || Packet::operator(0, 5, vec![Packet::literal(0, 3), Packet::operator(0, SUM, vec![Packet::literal(0, 1), Packet::literal(0, 1)])]);
+
+        // 1 + (2 + x) + (x + 0) => 1 + 2 + x + x
+        let packet = Packet::operator(0, SUM, vec![
+            Packet::literal(0, 1),
+            Packet::operator(0, SUM, vec![Packet::literal(0, 2), x()]),
+            Packet::operator(0, SUM, vec![x(), Packet::literal(0, 0)]),
+        ]);
+        let simplified = simplify(packet);
+        assert_eq!(simplified.type_id, SUM);
+        assert!(matches!(&simplified.contents, PacketContents::SubPackets(operands) if operands.len() == 4));
+        assert_eq!(simplified.value(), 5);
+
+        // min(max(x)) * 1 => x
+        let packet = Packet::operator(0, PRODUCT, vec![Packet::operator(0, MINIMUM, vec![Packet::operator(0, MAXIMUM, vec![x()])]), Packet::literal(0, 1)]);
+        assert_eq!(simplify(packet).type_id, 5);
+
+        // 1 * 1 => 1
+        let packet = Packet::operator(0, PRODUCT, vec![Packet::literal(0, 1), Packet::literal(0, 1)]);
+        assert_eq!(literal_value(&simplify(packet)), Some(1));
+
+        // 5 < 15 => 1
+        let simplified = simplify(Packet::from_hex_string("D8005AC2A8F0").unwrap());
+        assert_eq!(literal_value(&simplified), Some(1));
+        assert_eq!(simplified.to_binary_string().len(), 11);
+    }
+
+    #[test]
+    fn value_is_preserved() {
+        let mut rng = XorShift64::from_seed(16);
+        let generator = PacketGenerator { max_depth: 4, max_subpackets: 3, max_literal: 3 };
+        for _ in 0..2_000 {
+            let packet = generator.packet(&mut rng);
+            let value = packet.value();
+            let encoded_length = packet.fold(&mut EncodedLength);
+
+            let simplified = simplify(packet);
+            assert_eq!(simplified.value(), value);
+            assert!(simplified.length_in_bits <= encoded_length);
+            assert_eq!(simplified.length_in_bits, simplified.to_binary_string().len());
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::io::{self, BufReader, Bytes, Read};
+
+use super::{Packet, PacketContents};
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(io::Error),
+    InvalidHexDigit { byte_offset: usize, byte: u8 },
+    UnexpectedEnd { bit_offset: u64 },
+    LiteralTooLarge { bit_offset: u64 },
+    SubpacketOverrun { bit_offset: u64 },
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Io(error) => write!(f, "unable to read transmission: {}", error),
+            DecodeError::InvalidHexDigit { byte_offset, byte } => write!(f, "invalid hex digit {:?} at byte {}", *byte as char, byte_offset),
+            DecodeError::UnexpectedEnd { bit_offset } => write!(f, "transmission ended inside a packet at bit {}", bit_offset),
+            DecodeError::LiteralTooLarge { bit_offset } => write!(f, "literal value starting at bit {} does not fit into 128 bits", bit_offset),
+            DecodeError::SubpacketOverrun { bit_offset } => write!(f, "sub-packets run past the declared operator length at bit {}", bit_offset),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<io::Error> for DecodeError {
+    fn from(error: io::Error) -> Self {
+        DecodeError::Io(error)
+    }
+}
+
+/// Reads a hex encoded transmission bit by bit, skipping any whitespace in between hex digits.
+struct HexBitReader<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    byte_offset: usize,
+    bit_offset: u64,
+    nibble: u8,
+    bits_left_in_nibble: u8,
+    // Zero hex digits (and the digit following them) read ahead while looking for the end of the transmission
+    replayed_zero_nibbles: usize,
+    replayed_nibble: Option<u8>,
+}
+
+impl<R: Read> HexBitReader<R> {
+    fn from_reader(reader: R) -> Self {
+        HexBitReader {
+            bytes: BufReader::new(reader).bytes(),
+            byte_offset: 0,
+            bit_offset: 0,
+            nibble: 0,
+            bits_left_in_nibble: 0,
+            replayed_zero_nibbles: 0,
+            replayed_nibble: None,
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<Option<u8>, DecodeError> {
+        match self.bytes.next() {
+            Some(byte) => {
+                self.byte_offset += 1;
+                Ok(Some(byte?))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn hex_digit(&self, byte: u8) -> Result<u8, DecodeError> {
+        match (byte as char).to_digit(16) {
+            Some(digit) => Ok(digit as u8),
+            None => Err(DecodeError::InvalidHexDigit { byte_offset: self.byte_offset - 1, byte }),
+        }
+    }
+
+    fn read_hex_digit(&mut self) -> Result<Option<u8>, DecodeError> {
+        while let Some(byte) = self.read_byte()? {
+            if !byte.is_ascii_whitespace() {
+                return self.hex_digit(byte).map(Some)
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_nibble(&mut self) -> Result<Option<u8>, DecodeError> {
+        if self.replayed_zero_nibbles > 0 {
+            self.replayed_zero_nibbles -= 1;
+            return Ok(Some(0))
+        }
+        if let Some(nibble) = self.replayed_nibble.take() {
+            return Ok(Some(nibble))
+        }
+        self.read_hex_digit()
+    }
+
+    fn read_bit(&mut self) -> Result<u128, DecodeError> {
+        if self.bits_left_in_nibble == 0 {
+            self.nibble = self.next_nibble()?.ok_or(DecodeError::UnexpectedEnd { bit_offset: self.bit_offset })?;
+            self.bits_left_in_nibble = 4;
+        }
+        self.bits_left_in_nibble -= 1;
+        self.bit_offset += 1;
+        Ok(((self.nibble >> self.bits_left_in_nibble) & 1) as u128)
+    }
+
+    fn read_bits(&mut self, n: usize) -> Result<u128, DecodeError> {
+        let mut value = 0;
+        for _ in 0..n {
+            value = (value << 1) | self.read_bit()?;
+        }
+        Ok(value)
+    }
+
+    /// Drops the rest of the current hex digit and reports whether another top-level packet follows.
+    /// Zero digits running up to whitespace or the end of the input are padding, so they are only read ahead
+    /// and replayed once a non-zero digit shows up behind them.
+    fn skip_padding(&mut self) -> Result<bool, DecodeError> {
+        self.bit_offset += self.bits_left_in_nibble as u64;
+        self.bits_left_in_nibble = 0;
+        if self.replayed_zero_nibbles > 0 || self.replayed_nibble.is_some() {
+            return Ok(true)
+        }
+        let mut zero_nibbles = 0;
+        while let Some(byte) = self.read_byte()? {
+            if byte.is_ascii_whitespace() {
+                self.bit_offset += 4 * zero_nibbles as u64;
+                zero_nibbles = 0;
+                continue
+            }
+            match self.hex_digit(byte)? {
+                0 => zero_nibbles += 1,
+                nibble => {
+                    self.replayed_zero_nibbles = zero_nibbles;
+                    self.replayed_nibble = Some(nibble);
+                    return Ok(true)
+                },
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum PacketEvent {
+    PacketStart { offset: u64, version: u128, type_id: u128 },
+    Literal { value: u128, end: u64 },
+    OperatorEnd { type_id: u128, end: u64 },
+}
+
+enum Remaining {
+    Bits { end: u64 },
+    Packets(u128),
+}
+
+struct OpenOperator {
+    type_id: u128,
+    remaining: Remaining,
+}
+
+/// Decodes a hex transmission from any reader into a flat stream of packet events.
+/// Any number of top-level packets may follow each other, each starting on a fresh hex digit,
+/// and transmissions padded with zeros may be separated by whitespace.
+pub struct PacketEvents<R: Read> {
+    reader: HexBitReader<R>,
+    open_operators: Vec<OpenOperator>,
+    queued: VecDeque<PacketEvent>,
+    finished: bool,
+}
+
+#[allow(dead_code)]
+impl<R: Read> PacketEvents<R> {
+    pub fn from_reader(reader: R) -> Self {
+        PacketEvents {
+            reader: HexBitReader::from_reader(reader),
+            open_operators: Vec::new(),
+            queued: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    fn read_literal(&mut self) -> Result<u128, DecodeError> {
+        let start = self.reader.bit_offset;
+        let mut literal = 0;
+        loop {
+            let group = self.reader.read_bits(5)?;
+            if literal >> 124 != 0 {
+                return Err(DecodeError::LiteralTooLarge { bit_offset: start })
+            }
+            literal = (literal << 4) | (group & 0b1111);
+            if group >> 4 == 0 {
+                return Ok(literal)
+            }
+        }
+    }
+
+    fn advance(&mut self) -> Result<bool, DecodeError> {
+        let offset = self.reader.bit_offset;
+        match self.open_operators.last_mut() {
+            Some(operator) => {
+                let done = match operator.remaining {
+                    Remaining::Bits { end } if offset > end => return Err(DecodeError::SubpacketOverrun { bit_offset: end }),
+                    Remaining::Bits { end } => offset == end,
+                    Remaining::Packets(0) => true,
+                    Remaining::Packets(ref mut left) => {
+                        *left -= 1;
+                        false
+                    },
+                };
+                if done {
+                    let type_id = operator.type_id;
+                    self.open_operators.pop();
+                    self.queued.push_back(PacketEvent::OperatorEnd { type_id, end: offset });
+                    return Ok(true)
+                }
+            },
+            None => {
+                if !self.reader.skip_padding()? {
+                    return Ok(false)
+                }
+            },
+        }
+
+        let offset = self.reader.bit_offset;
+        let version = self.reader.read_bits(3)?;
+        let type_id = self.reader.read_bits(3)?;
+        self.queued.push_back(PacketEvent::PacketStart { offset, version, type_id });
+        if type_id == 4 {
+            let value = self.read_literal()?;
+            self.queued.push_back(PacketEvent::Literal { value, end: self.reader.bit_offset });
+        } else {
+            let remaining = match self.reader.read_bits(1)? {
+                0 => {
+                    let total_length_in_bits = self.reader.read_bits(15)? as u64;
+                    Remaining::Bits { end: self.reader.bit_offset + total_length_in_bits }
+                },
+                _ => Remaining::Packets(self.reader.read_bits(11)?),
+            };
+            self.open_operators.push(OpenOperator { type_id, remaining });
+        }
+        Ok(true)
+    }
+}
+
+impl<R: Read> Iterator for PacketEvents<R> {
+    type Item = Result<PacketEvent, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.queued.pop_front() {
+            return Some(Ok(event))
+        }
+        if self.finished {
+            return None
+        }
+        match self.advance() {
+            Ok(true) => self.queued.pop_front().map(Ok),
+            Ok(false) => {
+                self.finished = true;
+                None
+            },
+            Err(error) => {
+                self.finished = true;
+                self.queued.clear();
+                Some(Err(error))
+            },
+        }
+    }
+}
+
+struct PartialPacket {
+    offset: u64,
+    version: u128,
+    type_id: u128,
+    subpackets: Vec<Packet>,
+}
+
+/// Assembles the events of a transmission into one `Packet` tree per top-level packet.
+pub struct Packets<R: Read> {
+    events: PacketEvents<R>,
+}
+
+#[allow(dead_code)]
+impl<R: Read> Packets<R> {
+    pub fn from_reader(reader: R) -> Self {
+        Packets { events: PacketEvents::from_reader(reader) }
+    }
+}
+
+impl<R: Read> Iterator for Packets<R> {
+    type Item = Result<Packet, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut open: Vec<PartialPacket> = Vec::new();
+        loop {
+            let event = match self.events.next()? {
+                Ok(event) => event,
+                Err(error) => return Some(Err(error)),
+            };
+            let packet = match event {
+                PacketEvent::PacketStart { offset, version, type_id } => {
+                    open.push(PartialPacket { offset, version, type_id, subpackets: Vec::new() });
+                    continue
+                },
+                PacketEvent::Literal { value, end } => {
+                    let partial = open.pop().unwrap();
+                    Packet {
+                        length_in_bits: (end - partial.offset) as usize,
+                        version: partial.version,
+                        type_id: partial.type_id,
+                        contents: PacketContents::Number(value),
+                    }
+                },
+                PacketEvent::OperatorEnd { type_id, end } => {
+                    let partial = open.pop().unwrap();
+                    Packet {
+                        length_in_bits: (end - partial.offset) as usize,
+                        version: partial.version,
+                        type_id,
+                        contents: PacketContents::SubPackets(partial.subpackets),
+                    }
+                },
+            };
+            match open.last_mut() {
+                Some(parent) => parent.subpackets.push(packet),
+                None => return Some(Ok(packet)),
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_events() {
+        let events: Vec<PacketEvent> = PacketEvents::from_reader("D2FE28".as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(events, vec![
+            PacketEvent::PacketStart { offset: 0, version: 6, type_id: 4 },
+            PacketEvent::Literal { value: 2021, end: 21 },
+        ]);
+    }
+
+    #[test]
+    fn operator_events() {
+        let events: Vec<PacketEvent> = PacketEvents::from_reader("38006F45291200".as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(events, vec![
+            PacketEvent::PacketStart { offset: 0, version: 1, type_id: 6 },
+            PacketEvent::PacketStart { offset: 22, version: 6, type_id: 4 },
+            PacketEvent::Literal { value: 10, end: 33 },
+            PacketEvent::PacketStart { offset: 33, version: 2, type_id: 4 },
+            PacketEvent::Literal { value: 20, end: 49 },
+            PacketEvent::OperatorEnd { type_id: 6, end: 49 },
+        ]);
+    }
+
+    #[test]
+    fn streamed_packets_match_in_memory_decoding() {
+        let transmissions = [
+            "8A004A801A8002F478",
+            "620080001611562C8802118E34",
+            "C0015000016115A2E0802F182340",
+            "A0016C880162017C3686B18A3D4780",
+            "9C0141080250320F1802104A08",
+        ];
+        let input = transmissions.join("\r\n") + "\n";
+        let packets: Vec<Packet> = Packets::from_reader(input.as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(packets.len(), transmissions.len());
+        for (packet, transmission) in packets.iter().zip(transmissions) {
+            let expected = Packet::from_hex_string(transmission).unwrap();
+            assert_eq!(packet.version_sum(), expected.version_sum());
+            assert_eq!(packet.value(), expected.value());
+            assert_eq!(packet.length_in_bits, expected.length_in_bits);
+        }
+    }
+
+    #[test]
+    fn malformed_transmissions() {
+        let mut packets = Packets::from_reader("D2FG28".as_bytes());
+        assert!(matches!(packets.next(), Some(Err(DecodeError::InvalidHexDigit { byte_offset: 3, byte: b'G' }))));
+        assert!(packets.next().is_none());
+
+        let mut packets = Packets::from_reader("38006F4529".as_bytes());
+        assert!(matches!(packets.next(), Some(Err(DecodeError::UnexpectedEnd { bit_offset: 40 }))));
+    }
+}
@@ -0,0 +1,131 @@
+use std::cmp::max;
+
+use super::fold::PacketFold;
+use super::Packet;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Push(u128),
+    Apply { type_id: u128, arity: usize },
+}
+
+/// Semantics of the operator instructions, keyed by the operator packet type ID.
+pub trait InstructionSet {
+    fn apply(&self, type_id: u128, operands: &[u128]) -> u128;
+}
+
+/// The operators from the puzzle: sum, product, min, max, greater than, less than and equal to.
+pub struct StandardOperators;
+
+impl InstructionSet for StandardOperators {
+    fn apply(&self, type_id: u128, operands: &[u128]) -> u128 {
+        Packet::get_operator_value(operands, type_id)
+    }
+}
+
+/// Flat post-order program for a stack machine, compiled once and run under any `InstructionSet`.
+pub struct Program {
+    instructions: Vec<Instruction>,
+    max_stack_size: usize,
+}
+
+#[allow(dead_code)]
+impl Program {
+    pub fn compile(packet: &Packet) -> Self {
+        let mut compiler = Compiler { instructions: Vec::new() };
+        let max_stack_size = packet.fold(&mut compiler);
+        Program { instructions: compiler.instructions, max_stack_size }
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn run<I: InstructionSet>(&self, instruction_set: &I) -> u128 {
+        let mut stack = Vec::with_capacity(self.max_stack_size);
+        for instruction in &self.instructions {
+            match *instruction {
+                Instruction::Push(value) => stack.push(value),
+                Instruction::Apply { type_id, arity } => {
+                    let operands_start = stack.len() - arity;
+                    let value = instruction_set.apply(type_id, &stack[operands_start..]);
+                    stack.truncate(operands_start);
+                    stack.push(value);
+                },
+            }
+        }
+        stack.pop().unwrap()
+    }
+}
+
+/// Emits instructions in post-order while folding, returning the stack size each subtree needs.
+struct Compiler {
+    instructions: Vec<Instruction>,
+}
+
+impl PacketFold for Compiler {
+    type Output = usize;
+
+    fn literal(&mut self, _packet: &Packet, value: u128) -> usize {
+        self.instructions.push(Instruction::Push(value));
+        1
+    }
+
+    fn operator(&mut self, packet: &Packet, subpackets: Vec<usize>) -> usize {
+        self.instructions.push(Instruction::Apply { type_id: packet.type_id(), arity: subpackets.len() });
+        subpackets.iter().enumerate().fold(1, |stack_size, (i, subpacket_stack_size)| max(stack_size, i + subpacket_stack_size))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SwappedMinMax;
+
+    impl InstructionSet for SwappedMinMax {
+        fn apply(&self, type_id: u128, operands: &[u128]) -> u128 {
+            match type_id {
+                2 => StandardOperators.apply(3, operands),
+                3 => StandardOperators.apply(2, operands),
+                _ => StandardOperators.apply(type_id, operands),
+            }
+        }
+    }
+
+    #[test]
+    fn compiles_post_order() {
+        let program = Program::compile(&Packet::from_hex_string("9C0141080250320F1802104A08").unwrap());
+        assert_eq!(program.instructions(), &[
+            Instruction::Push(1),
+            Instruction::Push(3),
+            Instruction::Apply { type_id: 0, arity: 2 },
+            Instruction::Push(2),
+            Instruction::Push(2),
+            Instruction::Apply { type_id: 1, arity: 2 },
+            Instruction::Apply { type_id: 7, arity: 2 },
+        ]);
+        assert_eq!(program.max_stack_size, 3);
+    }
+
+    #[test]
+    fn matches_tree_walk() {
+        for hex_string in ["C200B40A82", "04005AC33890", "880086C3E88112", "CE00C43D881120", "D8005AC2A8F0", "F600BC2D8F", "9C005AC2F8F0", "9C0141080250320F1802104A08"] {
+            let packet = Packet::from_hex_string(hex_string).unwrap();
+            assert_eq!(Program::compile(&packet).run(&StandardOperators), packet.value());
+        }
+    }
+
+    #[test]
+    fn custom_instruction_set() {
+        // min(7, 8, 9) and max(1, 2, 3)
+        let program = Program::compile(&Packet::from_hex_string("880086C3E88112").unwrap());
+        assert_eq!(program.run(&StandardOperators), 7);
+        assert_eq!(program.run(&SwappedMinMax), 9);
+
+        let program = Program::compile(&Packet::from_hex_string("CE00C43D881120").unwrap());
+        assert_eq!(program.run(&SwappedMinMax), 7);
+    }
+}
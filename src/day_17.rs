@@ -1,52 +1,100 @@
-use std::cmp::max;
-use std::collections::HashMap;
 use crate::array_2d::Coordinate;
 
+mod analytic;
+mod parse;
+mod physics;
+mod render;
+mod scene;
+mod sweep;
+
+use analytic::{StepWindow, FOREVER};
+pub use physics::Physics;
+use physics::velocity_bounds;
+use sweep::SweepError;
+
+/// Panic message of the puzzle answers for targets without a finite answer, see `velocity_bounds`.
+const UNBOUNDED_TARGET: &str = "target touches the launcher's row: infinitely many velocities hit it";
+
 pub fn part_1(input: &str) -> isize {
     // One time tick after reaching 0 in y, we get to position (-v_y - 1), since it's reverse of how we went up in the start
         // Since we want to maximize y_max = (v_y^2 + v_y) / 2, which is increasing, we simply find highest v_y s.t. (-v_y - 1) >= bottom, i.e., v_y = (-bottom - 1)
-    // v_x is then one that gets "stuck" on any x level within
+    // A target above the launcher is passed on the way up and down at the same heights, and the first step up already jumps by v_y
+        // So the highest v_y that can still hit it is v_y = top, hitting it right in the first step
+    // v_x is then one that gets "stuck" on any x level within (or any v_x within the target for the first step hit)
     let area = Area2D::from_input(input);
-    let best_v_y = -area.lower_right.y() - 1;
+    assert!(velocity_bounds(&area, &Physics::default()).is_some(), "{}", UNBOUNDED_TARGET);
+    let best_v_y = if area.max_y() < 0 { -area.min_y() - 1 } else { area.max_y() };
     (best_v_y.pow(2) + best_v_y) / 2
 }
 
 pub fn part_2(input: &str) -> isize {
     let area = Area2D::from_input(input);
+    let probe = Probe::from_area(&area, &Physics::default()).expect(UNBOUNDED_TARGET);
+    probe.count_hits() as isize
+}
 
-    // Must not overshoot in one step
-    let max_vx = area.lower_right.x();
+pub fn part_2_naive(input: &str) -> isize {
+    let area = Area2D::from_input(input);
+    let physics = Physics::default();
+    let (vx_range, vy_range) = velocity_bounds(&area, &physics).expect(UNBOUNDED_TARGET);
+    let (min_vx, max_vx) = vx_range.into_inner();
+    let (min_vy, max_vy) = vy_range.into_inner();
 
-    // Must not stall the x coordinate of the probe before reaching the left edge of the target
-    let min_vx = ((-1.0 + f64::sqrt(1.0 + 8.0 * area.upper_left.x() as f64)) / 2.0).ceil() as isize;
+    let mut num_results = 0;
+    for vx in min_vx..=max_vx {
+        for vy in min_vy..=max_vy {
+            num_results += simulate(vx, vy, &area, &physics) as isize;
+        }
+    }
 
-    // Must not "overshoot" during (i.e., use same v_y as in pt1)
-    let max_vy = -area.lower_right.y() - 1;
+    num_results
+}
 
-    // Must not "overshoot" in one step
-    let min_vy = area.lower_right.y();
+/// Counts the hitting velocities for the target area in the file `args[0]` with the parallel sweep on `args[1]` threads,
+/// all available ones by default, reporting the finished `vx` columns on stderr.
+pub fn print_sweep(args: &[String]) -> Result<(), SweepError> {
+    let file = args.first().ok_or(SweepError::MissingInput)?;
+    let num_threads = match args.get(1) {
+        Some(text) => text.parse().ok().filter(|&num_threads| num_threads > 0).ok_or_else(|| SweepError::InvalidThreads { text: text.clone() })?,
+        None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+    let input = std::fs::read_to_string(file).map_err(|error| SweepError::Unreadable { file: file.clone(), error })?;
+    let area: Area2D = input.parse().map_err(|error| SweepError::InvalidArea { file: file.clone(), error })?;
 
-    let mut num_results = 0;
+    let progress = |finished, total| eprint!("\r{}/{} columns", finished, total);
+    let num_hits = sweep::count_hits_parallel(&area, num_threads, Some(&progress)).ok_or_else(|| SweepError::Unbounded { file: file.clone() })?;
+    eprintln!();
+    println!("{}", num_hits);
+    Ok(())
+}
 
-    // Memo version - slower
-    // let mut probe = Probe::from_area(&area);
-    // for vx in min_vx..=max_vx {
-    //     for vy in min_vy..=max_vy {
-    //         num_results += probe.will_hit_target(0, 0, vx, vy);
-    //     }
-    // }
+pub fn part_2_parallel(input: &str) -> isize {
+    let area = Area2D::from_input(input);
+    let num_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    sweep::count_hits_parallel(&area, num_threads, None).expect(UNBOUNDED_TARGET) as isize
+}
 
-    // Naive version - faster
-    for vx in min_vx..=max_vx {
-        for vy in min_vy..=max_vy {
-            num_results += simulate(0, 0, vx, vy, &area);
+/// All initial velocities with which the probe ends up within the target at some step, or `None` if there are infinitely many.
+#[allow(dead_code)]
+pub fn hitting_velocities(area: &Area2D, physics: &Physics) -> Option<Vec<Coordinate>> {
+    let (vx_range, vy_range) = velocity_bounds(area, physics)?;
+    let mut velocities = Vec::new();
+    for vx in vx_range {
+        for vy in vy_range.clone() {
+            if simulate(vx, vy, area, physics) {
+                velocities.push(Coordinate::from_coordinates(vx, vy));
+            }
         }
     }
+    Some(velocities)
+}
 
-    num_results
+pub fn part_2_analytic(input: &str) -> isize {
+    let area = Area2D::from_input(input);
+    analytic::count_hits(&area).expect(UNBOUNDED_TARGET) as isize
 }
 
-struct Area2D {
+pub struct Area2D {
     upper_left: Coordinate,
     lower_right: Coordinate,
 }
@@ -54,66 +102,200 @@ struct Area2D {
 #[allow(dead_code)]
 impl Area2D {
 
+    pub fn from_bounds(min_x: isize, max_x: isize, min_y: isize, max_y: isize) -> Self {
+        Area2D {
+            upper_left: Coordinate::from_coordinates(min_x, max_y),
+            lower_right: Coordinate::from_coordinates(max_x, min_y),
+        }
+    }
+
+    pub fn translated(&self, dx: isize, dy: isize) -> Self {
+        Self::from_bounds(self.min_x() + dx, self.max_x() + dx, self.min_y() + dy, self.max_y() + dy)
+    }
+
+    /// The same area reflected along the y axis.
+    pub fn mirrored_x(&self) -> Self {
+        Self::from_bounds(-self.max_x(), -self.min_x(), self.min_y(), self.max_y())
+    }
+
+    pub fn min_x(&self) -> isize {
+        self.upper_left.x()
+    }
+
+    pub fn max_x(&self) -> isize {
+        self.lower_right.x()
+    }
+
+    pub fn min_y(&self) -> isize {
+        self.lower_right.y()
+    }
+
+    pub fn max_y(&self) -> isize {
+        self.upper_left.y()
+    }
+
     pub fn coordinates_within(&self, x: isize, y: isize) -> bool {
         self.upper_left.x() <= x && x <= self.lower_right.x() && y <= self.upper_left.y() && self.lower_right.y() <= y
     }
 
-    pub fn from_input(input_str: &str) -> Self {
-        let input = input_str.trim_start_matches("target area: ").trim_end_matches("\n");
-        let parts: Vec<&str> = input.split(", ").collect();
-        let x_range: Vec<isize> = parts[0].trim_start_matches("x=").split("..").map(|s| s.parse().unwrap()).collect();
-        let y_range: Vec<isize> = parts[1].trim_start_matches("y=").split("..").map(|s| s.parse().unwrap()).collect();
-        Area2D {
-            upper_left: Coordinate::from_coordinates(x_range[0], y_range[1]),
-            lower_right: Coordinate::from_coordinates(x_range[1], y_range[0]),
-        }
+    pub fn from_input(input: &str) -> Self {
+        input.parse().unwrap_or_else(|error| panic!("Invalid target area: {}", error))
     }
 }
 
-fn simulate(mut x: isize, mut y: isize, mut vx: isize, mut vy: isize, target_area: &Area2D) -> isize{
-    while !((x > target_area.lower_right.x()) || (y < target_area.lower_right.y() && vy <= 0) || (x < target_area.upper_left.x() && vx == 0)) {
-        if target_area.coordinates_within(x, y) { return 1 }
-        x += vx;
-        y += vy;
-        vx = max(0, vx - 1);
-        vy -= 1;
+fn simulate(vx: isize, vy: isize, target_area: &Area2D, physics: &Physics) -> bool {
+    let mut position = physics.launch();
+    let mut velocity = Coordinate::from_coordinates(vx, vy);
+    while !physics.out_of_reach(target_area, position, velocity) {
+        if target_area.coordinates_within(position.x(), position.y()) { return true }
+        physics.step(&mut position, &mut velocity);
     }
-    0
+    false
 }
 
-#[allow(dead_code)]
-struct Probe<'a> {
-    target_area: &'a Area2D,
-    memo: HashMap<[isize; 4], isize>,  // x, y, vx, vy
+/// Positions of a probe per step, from the launch until the first position from which the target is out of reach.
+#[derive(Debug)]
+pub struct Trajectory {
+    velocity: Coordinate,
+    positions: Vec<Coordinate>,
+    first_hit: Option<usize>,
+    physics: Physics,
 }
 
 #[allow(dead_code)]
-impl<'a> Probe<'a> {
+impl Trajectory {
 
-    pub fn from_area(area: &'a Area2D) -> Self {
-        Probe {
-            target_area: area,
-            memo: HashMap::new(),
+    pub fn from_launch(vx: isize, vy: isize, target_area: &Area2D, physics: &Physics) -> Self {
+        let mut position = physics.launch();
+        let mut velocity = Coordinate::from_coordinates(vx, vy);
+        let mut positions = vec![position];
+        let mut first_hit = None;
+        while !physics.out_of_reach(target_area, position, velocity) {
+            if first_hit.is_none() && target_area.coordinates_within(position.x(), position.y()) {
+                first_hit = Some(positions.len() - 1);
+            }
+            physics.step(&mut position, &mut velocity);
+            positions.push(position);
         }
+        Trajectory { velocity: Coordinate::from_coordinates(vx, vy), positions, first_hit, physics: *physics }
     }
 
-    pub fn will_hit_target(&mut self, x: isize, y: isize, vx: isize, vy: isize) -> isize {
-        if let Some(&memo_value) = self.memo.get(&[x, y, vx, vy]) {
-            return memo_value
-        }
+    pub fn velocity(&self) -> Coordinate {
+        self.velocity
+    }
+
+    pub fn positions(&self) -> &[Coordinate] {
+        &self.positions
+    }
+
+    /// Step at which the probe is within the target for the first time.
+    pub fn first_hit(&self) -> Option<usize> {
+        self.first_hit
+    }
 
-        if self.target_area.coordinates_within(x, y) {
-            return 1
+    pub fn hits(&self) -> bool {
+        self.first_hit.is_some()
+    }
+
+    /// Highest y the probe reaches, even if the recorded positions stop before the probe gets there,
+    /// or `None` if it keeps rising without gravity.
+    pub fn apex(&self) -> Option<isize> {
+        self.physics.apex(self.velocity)
+    }
+}
+
+fn overlap(a: &StepWindow, b: &StepWindow) -> bool {
+    a.first <= b.last && b.first <= a.last
+}
+
+/// Memoised solver. The axes of the probe move independently, so rather than simulating every pair of velocities,
+/// each x and each y velocity is simulated once for the steps it spends within the target along that axis.
+/// A pair of velocities then hits exactly when their step windows overlap.
+struct Probe {
+    min_vx: isize,
+    min_vy: isize,
+    x_windows: Vec<Option<StepWindow>>,  // indexed by vx - min_vx
+    y_windows: Vec<[Option<StepWindow>; 2]>,  // indexed by vy - min_vy, rising and falling through a target above
+}
+
+#[allow(dead_code)]
+impl Probe {
+
+    /// `None` if infinitely many velocities hit, see `velocity_bounds`.
+    pub fn from_area(area: &Area2D, physics: &Physics) -> Option<Self> {
+        let (vx_range, vy_range) = velocity_bounds(area, physics)?;
+        let area = area.translated(-physics.launch().x(), -physics.launch().y());
+        Some(Probe {
+            min_vx: *vx_range.start(),
+            min_vy: *vy_range.start(),
+            x_windows: vx_range.map(|vx| Self::x_window(vx, &area, physics)).collect(),
+            y_windows: vy_range.map(|vy| Self::y_windows(vy, &area, physics)).collect(),
+        })
+    }
+
+    fn x_window(mut vx: isize, area: &Area2D, physics: &Physics) -> Option<StepWindow> {
+        let (mut x, mut step) = (0, 0);
+        let mut first = None;
+        loop {
+            let within = area.min_x() <= x && x <= area.max_x();
+            if within && vx == 0 {
+                return first.or(Some(step)).map(|first| StepWindow { first, last: FOREVER })
+            }
+            if within && first.is_none() {
+                first = Some(step);
+            }
+            if !within && first.is_some() {
+                return first.map(|first| StepWindow { first, last: step - 1 })
+            }
+            if vx == 0 || (vx > 0 && area.max_x() < x) || (vx < 0 && x < area.min_x()) {
+                return None
+            }
+            x += vx;
+            vx = vx.signum() * (vx.abs() - physics.x_drag()).max(0);
+            step += 1;
         }
+    }
 
-        if (x > self.target_area.lower_right.x()) || (y < self.target_area.lower_right.y() && vy <= 0) || (x < self.target_area.upper_left.x() && vx == 0) {
-            self.memo.insert([x, y, vx, vy], 0);
-            return 0
+    fn y_windows(vy: isize, area: &Area2D, physics: &Physics) -> [Option<StepWindow>; 2] {
+        // Parked within the target in x, only the y coordinate can take the probe out of reach
+        let mut position = Coordinate::from_coordinates(area.min_x(), 0);
+        let mut velocity = Coordinate::from_coordinates(0, vy);
+        let mut windows = [None, None];
+        let (mut num_windows, mut step) = (0, 0);
+        let mut first = None;
+        while !physics.out_of_reach(area, position, velocity) {
+            let within = area.min_y() <= position.y() && position.y() <= area.max_y();
+            match (within, first) {
+                (true, None) => first = Some(step),
+                (false, Some(window_first)) => {
+                    windows[num_windows] = Some(StepWindow { first: window_first, last: step - 1 });
+                    num_windows += 1;
+                    first = None;
+                },
+                _ => {},
+            }
+            physics.step(&mut position, &mut velocity);
+            step += 1;
+        }
+        if let Some(window_first) = first {
+            windows[num_windows] = Some(StepWindow { first: window_first, last: step - 1 });
         }
+        windows
+    }
 
-        let res = self.will_hit_target(x + vx, y + vy, max(0, vx - 1), vy - 1);
-        self.memo.insert([x, y, vx, vy], res);
-        res
+    pub fn will_hit_target(&self, vx: isize, vy: isize) -> bool {
+        let Some(x_window) = &self.x_windows[(vx - self.min_vx) as usize] else { return false };
+        self.y_windows[(vy - self.min_vy) as usize].iter().flatten().any(|y_window| overlap(x_window, y_window))
+    }
+
+    pub fn count_hits(&self) -> usize {
+        let mut num_hits = 0;
+        for x_window in self.x_windows.iter().flatten() {
+            for y_windows in &self.y_windows {
+                num_hits += y_windows.iter().flatten().any(|y_window| overlap(x_window, y_window)) as usize;
+            }
+        }
+        num_hits
     }
 }
 
@@ -127,6 +309,123 @@ mod tests {
         assert_eq!(part_2("target area: x=20..30, y=-10..-5"), 112);
     }
 
+    fn brute_force(input: &str) -> (isize, isize) {
+        let area = Area2D::from_input(input);
+        let mut num_hits = 0;
+        let mut best_apex = isize::MIN;
+        for vx in -70..=70 {
+            for vy in -70..=70 {
+                if simulate(vx, vy, &area, &Physics::default()) {
+                    num_hits += 1;
+                    best_apex = best_apex.max(if vy > 0 { vy * (vy + 1) / 2 } else { 0 });
+                }
+            }
+        }
+        (best_apex, num_hits)
+    }
+
+    #[test]
+    fn all_quadrants_work() {
+        for input in [
+            "target area: x=20..30, y=-10..-5",
+            "target area: x=-30..-20, y=-10..-5",
+            "target area: x=20..30, y=5..10",
+            "target area: x=-30..-20, y=5..10",
+            "target area: x=-6..4, y=-10..-5",
+            "target area: x=-4..6, y=5..10",
+            "target area: x=0..0, y=-10..-5",
+            "target area: x=-3..-1, y=1..1",
+            "target area: x=17..25, y=3..20",
+        ] {
+            let (best_apex, num_hits) = brute_force(input);
+            assert_eq!(part_1(input), best_apex, "{}", input);
+            assert_eq!(part_2(input), num_hits, "{}", input);
+            assert_eq!(part_2_naive(input), num_hits, "{}", input);
+            assert_eq!(part_2_analytic(input), num_hits, "{}", input);
+        }
+        assert_eq!(part_1("target area: x=-30..-20, y=-10..-5"), 45);
+        assert_eq!(part_2("target area: x=-30..-20, y=-10..-5"), 112);
+    }
+
+    #[test]
+    fn memoised_probe_works() {
+        let area = Area2D::from_input("target area: x=-30..-20, y=5..10");
+        for physics in [
+            Physics::default(),
+            Physics::default().with_launch(-3, 2).with_gravity(2),
+            Physics::default().with_gravity(0).with_x_drag(0),
+            Physics::default().with_x_drag(0).with_terminal_velocity(2),
+        ] {
+            let probe = Probe::from_area(&area, &physics).unwrap();
+            let velocities = hitting_velocities(&area, &physics).unwrap();
+            assert_eq!(probe.count_hits(), velocities.len(), "{:?}", physics);
+            assert!(velocities.iter().all(|velocity| probe.will_hit_target(velocity.x(), velocity.y())));
+        }
+    }
+
+    #[test]
+    fn trajectories_work() {
+        let area = Area2D::from_input("target area: x=20..30, y=-10..-5");
+        let trajectory = Trajectory::from_launch(7, 2, &area, &Physics::default());
+        let path: Vec<(isize, isize)> = trajectory.positions().iter().map(|position| (position.x(), position.y())).collect();
+        assert_eq!(path[..8], [(0, 0), (7, 2), (13, 3), (18, 3), (22, 2), (25, 0), (27, -3), (28, -7)]);
+        assert_eq!(trajectory.first_hit(), Some(7));
+        assert_eq!(trajectory.apex(), Some(3));
+
+        assert_eq!(Trajectory::from_launch(6, 9, &area, &Physics::default()).apex(), Some(45));
+        assert!(!Trajectory::from_launch(17, -4, &area, &Physics::default()).hits());
+        assert_eq!(Trajectory::from_launch(17, -4, &area, &Physics::default()).positions()[1], Coordinate::from_coordinates(17, -4));
+    }
+
+    #[test]
+    fn hitting_velocities_match_part_1_and_2() {
+        for input in ["target area: x=20..30, y=-10..-5", "target area: x=-30..-20, y=5..10", "target area: x=-6..4, y=-10..-5"] {
+            let area = Area2D::from_input(input);
+            let velocities = hitting_velocities(&area, &Physics::default()).unwrap();
+            assert_eq!(velocities.len() as isize, part_2(input));
+
+            let trajectories: Vec<Trajectory> = velocities.iter().map(|v| Trajectory::from_launch(v.x(), v.y(), &area, &Physics::default())).collect();
+            assert!(trajectories.iter().all(Trajectory::hits));
+            assert_eq!(trajectories.iter().filter_map(Trajectory::apex).max(), Some(part_1(input)));
+        }
+        let velocities = hitting_velocities(&Area2D::from_input("target area: x=20..30, y=-10..-5"), &Physics::default()).unwrap();
+        assert!(velocities.contains(&Coordinate::from_coordinates(6, 9)));
+        assert!(velocities.contains(&Coordinate::from_coordinates(7, -1)));
+    }
+
+    #[test]
+    fn sweep_runner_rejects_bad_arguments() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert!(matches!(print_sweep(&[]), Err(SweepError::MissingInput)));
+        assert!(matches!(print_sweep(&args(&["inputs/17.in", "0"])), Err(SweepError::InvalidThreads { .. })));
+        assert!(matches!(print_sweep(&args(&["missing/17.in"])), Err(SweepError::Unreadable { .. })));
+    }
+
+    #[test]
+    fn targets_on_launcher_row_are_unbounded() {
+        // Every probe launched upwards passes the launcher's row again on its way down
+        let area = Area2D::from_input("target area: x=20..30, y=-5..5");
+        assert_eq!(velocity_bounds(&area, &Physics::default()), None);
+        assert_eq!(hitting_velocities(&area, &Physics::default()), None);
+        assert!((2..100).all(|vy| simulate(6, vy, &area, &Physics::default())));
+    }
+
+    #[test]
+    #[should_panic(expected = "infinitely many velocities")]
+    fn part_1_rejects_targets_on_launcher_row() {
+        part_1("target area: x=20..30, y=-5..5");
+    }
+
+    #[test]
+    fn part_2_variants_reject_targets_on_launcher_row() {
+        let part_2_variants: [fn(&str) -> isize; 4] = [part_2, part_2_naive, part_2_analytic, part_2_parallel];
+        for part_2_variant in part_2_variants {
+            let panic = std::panic::catch_unwind(|| part_2_variant("target area: x=20..30, y=-5..5")).unwrap_err();
+            let message = panic.downcast_ref::<String>().map(String::as_str).or(panic.downcast_ref::<&str>().copied());
+            assert_eq!(message, Some(UNBOUNDED_TARGET));
+        }
+    }
+
     #[test]
     fn pt_1_works() {
         assert_eq!(part_1("target area: x=257..286, y=-101..-57"), 5_050);
@@ -135,5 +434,6 @@ mod tests {
     #[test]
     fn pt_2_works() {
         assert_eq!(part_2("target area: x=257..286, y=-101..-57"), 2_223);
+        assert_eq!(part_2_analytic("target area: x=257..286, y=-101..-57"), 2_223);
     }
 }
\ No newline at end of file
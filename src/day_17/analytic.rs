@@ -0,0 +1,271 @@
+use std::cmp::min;
+
+use crate::array_2d::Coordinate;
+use super::{velocity_bounds, Area2D, Physics};
+
+/// Marks a window that never closes, e.g. for a probe whose x coordinate stalls inside the target.
+pub const FOREVER: usize = usize::MAX;
+
+/// Steps `first..=last` during which the probe is within the target range along one axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepWindow {
+    pub first: usize,
+    pub last: usize,
+}
+
+fn x_position(vx: isize, step: isize) -> isize {
+    let step = min(step, vx);
+    step * vx - step * (step - 1) / 2
+}
+
+fn y_position(vy: isize, step: isize) -> isize {
+    step * vy - step * (step - 1) / 2
+}
+
+/// Position and velocity of a probe launched at `(vx, vy)` from (0,0) under the puzzle rules after `step` steps.
+pub fn state_at(vx: isize, vy: isize, step: isize) -> (Coordinate, Coordinate) {
+    let x = vx.signum() * x_position(vx.abs(), step);
+    let current_vx = vx.signum() * (vx.abs() - step).max(0);
+    (Coordinate::from_coordinates(x, y_position(vy, step)), Coordinate::from_coordinates(current_vx, vy - step))
+}
+
+/// Smallest step from `low` on at which the monotone `reached` holds. The closed-form `guess`
+/// is at most a step or two off, so the search only has to walk a few steps from there.
+fn first_step<F: Fn(isize) -> bool>(low: isize, guess: f64, reached: F) -> isize {
+    let mut step = (guess.ceil() as isize).max(low);
+    while step > low && reached(step - 1) {
+        step -= 1;
+    }
+    while !reached(step) {
+        step += 1;
+    }
+    step
+}
+
+/// Steps in which the x coordinate is within the target.
+pub fn x_window(vx: isize, area: &Area2D) -> Option<StepWindow> {
+    if vx < 0 {
+        return x_window(-vx, &area.mirrored_x())
+    }
+    let stall_x = vx * (vx + 1) / 2;
+    if stall_x < area.min_x() || area.max_x() < 0 {
+        return None
+    }
+
+    // Solves step * vx - step * (step - 1) / 2 = x for the step before the probe stalls
+    let b = (2 * vx + 1) as f64;
+    let entering_step = |x: isize| (b - (b * b - 8.0 * x as f64).max(0.0).sqrt()) / 2.0;
+
+    let first = first_step(0, entering_step(area.min_x()), |step| x_position(vx, step) >= area.min_x());
+    if x_position(vx, first) > area.max_x() {
+        return None
+    }
+    let last = if stall_x <= area.max_x() {
+        FOREVER
+    } else {
+        (first_step(first, entering_step(area.max_x() + 1), |step| x_position(vx, step) > area.max_x()) - 1) as usize
+    };
+    Some(StepWindow { first: first as usize, last })
+}
+
+fn window(first: isize, last: isize) -> Option<StepWindow> {
+    if first > last {
+        return None
+    }
+    Some(StepWindow { first: first as usize, last: last as usize })
+}
+
+/// Steps in which the y coordinate is within the target. A target above the launcher can be
+/// passed once on the way up and once on the way down, so there may be two separate windows.
+pub fn y_windows(vy: isize, area: &Area2D) -> [Option<StepWindow>; 2] {
+    // Solves step * vy - step * (step - 1) / 2 = y on the way up and on the way down
+    let b = (2 * vy + 1) as f64;
+    let rising_step = |y: isize| (b - (b * b - 8.0 * y as f64).max(0.0).sqrt()) / 2.0;
+    let falling_step = |y: isize| (b + (b * b - 8.0 * y as f64).max(0.0).sqrt()) / 2.0;
+
+    if area.max_y() < 0 {
+        let first = first_step(0, falling_step(area.max_y()), |step| y_position(vy, step) <= area.max_y());
+        let last = first_step(first, falling_step(area.min_y() - 1), |step| y_position(vy, step) < area.min_y()) - 1;
+        return [window(first, last), None]
+    }
+
+    let apex = vy * (vy + 1) / 2;
+    if vy <= 0 || apex < area.min_y() {
+        return [None, None]
+    }
+    // From the apex at steps vy and vy + 1 on the probe is falling
+    let rising_first = first_step(0, rising_step(area.min_y()), |step| y_position(vy, step) >= area.min_y());
+    let falling_last = first_step(vy + 1, falling_step(area.min_y() - 1), |step| y_position(vy, step) < area.min_y()) - 1;
+    if apex <= area.max_y() {
+        return [window(rising_first, falling_last), None]
+    }
+    let rising_last = first_step(rising_first, rising_step(area.max_y() + 1), |step| y_position(vy, step) > area.max_y()) - 1;
+    let falling_first = first_step(vy + 1, falling_step(area.max_y()), |step| y_position(vy, step) <= area.max_y());
+    [window(rising_first, rising_last), window(falling_first, falling_last)]
+}
+
+fn intersection(a: StepWindow, b: StepWindow) -> Option<StepWindow> {
+    window(a.first.max(b.first) as isize, a.last.min(b.last) as isize)
+}
+
+/// Steps at which a probe launched at `(vx, vy)` from (0,0) under the puzzle rules is within the target, found by
+/// intersecting the step windows of both axes. There are at most two separate windows, and none for a miss.
+#[allow(dead_code)]
+pub fn hit_steps(vx: isize, vy: isize, area: &Area2D) -> Vec<StepWindow> {
+    let Some(x_window) = x_window(vx, area) else { return Vec::new() };
+    y_windows(vy, area).into_iter().flatten().filter_map(|y_window| intersection(x_window, y_window)).collect()
+}
+
+/// Counts x windows that open no later than `entering_by` and are still open at `open_at`, summed over all
+/// queries. Sweeps the queries by `entering_by`, keeping the window ends seen so far in a Fenwick tree.
+fn count_spanning(x_windows: &[StepWindow], mut queries: Vec<(usize, usize)>) -> usize {
+    let mut windows = x_windows.to_vec();
+    windows.sort_unstable_by_key(|window| window.first);
+    let mut lasts: Vec<usize> = windows.iter().map(|window| window.last).collect();
+    lasts.sort_unstable();
+    lasts.dedup();
+    queries.sort_unstable();
+
+    let mut tree = vec![0; lasts.len() + 1];
+    let mut num_entered = 0;
+    let mut num_spanning = 0;
+    for (entering_by, open_at) in queries {
+        while num_entered < windows.len() && windows[num_entered].first <= entering_by {
+            let mut i = lasts.partition_point(|&last| last < windows[num_entered].last) + 1;
+            while i < tree.len() {
+                tree[i] += 1;
+                i += i & i.wrapping_neg();
+            }
+            num_entered += 1;
+        }
+        let mut closed_before = 0;
+        let mut i = lasts.partition_point(|&last| last < open_at);
+        while i > 0 {
+            closed_before += tree[i];
+            i -= i & i.wrapping_neg();
+        }
+        num_spanning += num_entered - closed_before;
+    }
+    num_spanning
+}
+
+/// Counts the initial velocities hitting the target without simulating a single trajectory: a velocity hits
+/// exactly when its x and y step windows overlap, so sorting all x windows by their ends lets each `vy` count
+/// its partners with two binary searches. Velocities whose x window overlaps both y windows of a target above
+/// the launcher are counted twice that way, and subtracted again afterwards. `None` if infinitely many velocities hit.
+pub fn count_hits(area: &Area2D) -> Option<usize> {
+    let (vx_range, vy_range) = velocity_bounds(area, &Physics::default())?;
+
+    let x_windows: Vec<StepWindow> = vx_range.filter_map(|vx| x_window(vx, area)).collect();
+    let mut firsts: Vec<usize> = x_windows.iter().map(|window| window.first).collect();
+    let mut lasts: Vec<usize> = x_windows.iter().map(|window| window.last).collect();
+    firsts.sort_unstable();
+    lasts.sort_unstable();
+
+    let mut num_hits = 0;
+    let mut double_counted = Vec::new();
+    for vy in vy_range {
+        let windows = y_windows(vy, area);
+        for window in windows.iter().flatten() {
+            let entering_too_late = firsts.len() - firsts.partition_point(|&first| first <= window.last);
+            let leaving_too_early = lasts.partition_point(|&last| last < window.first);
+            num_hits += firsts.len() - entering_too_late - leaving_too_early;
+        }
+        if let [Some(rising), Some(falling)] = windows {
+            double_counted.push((rising.last, falling.first));
+        }
+    }
+    Some(num_hits - count_spanning(&x_windows, double_counted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_17::{part_2, simulate, Probe, Trajectory};
+    use crate::rng::XorShift64;
+
+    #[test]
+    fn windows() {
+        let area = Area2D::from_input("target area: x=20..30, y=-10..-5");
+        assert_eq!(x_window(6, &area), Some(StepWindow { first: 5, last: FOREVER }));
+        assert_eq!(x_window(7, &area), Some(StepWindow { first: 4, last: FOREVER }));
+        assert_eq!(x_window(9, &area), Some(StepWindow { first: 3, last: 4 }));
+        assert_eq!(x_window(17, &area), None);
+        assert_eq!(x_window(5, &area), None);
+
+        assert_eq!(y_windows(2, &area), [Some(StepWindow { first: 7, last: 7 }), None]);
+        assert_eq!(y_windows(9, &area), [Some(StepWindow { first: 20, last: 20 }), None]);
+        assert_eq!(y_windows(-4, &area), [Some(StepWindow { first: 2, last: 2 }), None]);
+        assert_eq!(y_windows(-5, &area), [Some(StepWindow { first: 1, last: 1 }), None]);
+        assert_eq!(y_windows(10, &area), [None, None]);
+
+        let area = Area2D::from_input("target area: x=-30..-20, y=5..10");
+        assert_eq!(x_window(-7, &area), Some(StepWindow { first: 4, last: FOREVER }));
+        assert_eq!(x_window(0, &area), None);
+
+        // Heights 0, 4, 7, 9, 10, 10, 9, 7, 4, 0
+        assert_eq!(y_windows(4, &area), [Some(StepWindow { first: 2, last: 7 }), None]);
+        // Heights 0, 5, 9, 12, 14, 15, 15, 14, 12, 9, 5, 0
+        assert_eq!(y_windows(5, &area), [Some(StepWindow { first: 1, last: 2 }), Some(StepWindow { first: 9, last: 10 })]);
+        assert_eq!(y_windows(11, &area), [None, None]);
+
+        let area = Area2D::from_input("target area: x=-3..5, y=-10..-5");
+        assert_eq!(x_window(0, &area), Some(StepWindow { first: 0, last: FOREVER }));
+        assert_eq!(x_window(3, &area), Some(StepWindow { first: 0, last: 2 }));
+    }
+
+    #[test]
+    fn hit_steps_agree_with_simulation() {
+        let mut rng = XorShift64::from_seed(38);
+        for _ in 0..100 {
+            let min_x = rng.range(-40, 40) as isize;
+            let max_x = min_x + rng.range(0, 20) as isize;
+            let min_y = if rng.chance(1, 2) { rng.range(-40, -1) } else { rng.range(1, 40) } as isize;
+            let max_y = if min_y < 0 { rng.range(min_y as i64, -1) } else { rng.range(min_y as i64, 60) } as isize;
+            let area = Area2D::from_bounds(min_x, max_x, min_y, max_y);
+            for _ in 0..100 {
+                let (vx, vy) = (rng.range(-45, 45) as isize, rng.range(-45, 45) as isize);
+                let trajectory = Trajectory::from_launch(vx, vy, &area, &Physics::default());
+                let expected: Vec<usize> = (0..trajectory.positions().len())
+                    .filter(|&step| area.coordinates_within(trajectory.positions()[step].x(), trajectory.positions()[step].y()))
+                    .collect();
+                let windows = hit_steps(vx, vy, &area);
+                let steps: Vec<usize> = windows.iter().flat_map(|window| window.first..=window.last).collect();
+                assert_eq!(steps, expected, "{} {} {}", area, vx, vy);
+                assert_eq!(!windows.is_empty(), simulate(vx, vy, &area, &Physics::default()), "{} {} {}", area, vx, vy);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_enumeration() {
+        let mut rng = XorShift64::from_seed(17);
+        for _ in 0..400 {
+            let min_x = rng.range(-60, 60) as isize;
+            let max_x = min_x + rng.range(0, 30) as isize;
+            let (min_y, max_y) = if rng.chance(1, 2) {
+                let min_y = rng.range(-60, -1);
+                (min_y as isize, (min_y + rng.range(0, -min_y - 1)) as isize)
+            } else {
+                let min_y = rng.range(1, 60);
+                (min_y as isize, (min_y + rng.range(0, 30)) as isize)
+            };
+            let input = format!("target area: x={}..{}, y={}..{}", min_x, max_x, min_y, max_y);
+            assert_eq!(count_hits(&Area2D::from_input(&input)).map(|num_hits| num_hits as isize), Some(part_2(&input)), "{}", input);
+        }
+    }
+
+    #[test]
+    fn scales_to_large_areas() {
+        // Exact against the memoised solver on areas that one still handles
+        for input in ["target area: x=1000..1400, y=-1400..-1000", "target area: x=-1400..-1000, y=1000..1400"] {
+            let area = Area2D::from_input(input);
+            assert_eq!(count_hits(&area), Probe::from_area(&area, &Physics::default()).map(|probe| probe.count_hits()), "{}", input);
+        }
+
+        // Every point of the target is hit directly in the first step, so that is a lower bound
+        let area = Area2D::from_input("target area: x=1000000..1200000, y=-1200000..-1000000");
+        let one_step_hits = 200_001 * 200_001;
+        assert!(count_hits(&area).unwrap() > one_step_hits);
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::{Display, Formatter};
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use super::Area2D;
+
+const PREFIX: &str = "target area:";
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseAreaError {
+    MissingPrefix,
+    MissingRange { axis: char },
+    DuplicateRange { axis: char },
+    UnexpectedRange { text: String },
+    InvalidRange { axis: char, text: String },
+    InvalidBound { axis: char, text: String, error: ParseIntError },
+}
+
+impl Display for ParseAreaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseAreaError::MissingPrefix => write!(f, "expected the area to start with {:?}", PREFIX),
+            ParseAreaError::MissingRange { axis } => write!(f, "missing the {}= range", axis),
+            ParseAreaError::DuplicateRange { axis } => write!(f, "the {}= range is given more than once", axis),
+            ParseAreaError::UnexpectedRange { text } => write!(f, "expected an x= or y= range but got {:?}", text),
+            ParseAreaError::InvalidRange { axis, text } => write!(f, "expected the {}= range in the form a..b but got {:?}", axis, text),
+            ParseAreaError::InvalidBound { axis, text, error } => write!(f, "invalid bound {:?} in the {}= range: {}", text, axis, error),
+        }
+    }
+}
+
+impl std::error::Error for ParseAreaError {}
+
+fn parse_bounds(axis: char, range: &str) -> Result<(isize, isize), ParseAreaError> {
+    let (start, end) = range.split_once("..").ok_or_else(|| ParseAreaError::InvalidRange { axis, text: range.trim().to_string() })?;
+    let parse_bound = |bound: &str| -> Result<isize, ParseAreaError> {
+        let bound = bound.trim();
+        bound.parse().map_err(|error| ParseAreaError::InvalidBound { axis, text: bound.to_string(), error })
+    };
+    let (start, end) = (parse_bound(start)?, parse_bound(end)?);
+    Ok((start.min(end), start.max(end)))
+}
+
+/// Parses `target area: x=a..b, y=c..d` with any whitespace around the tokens, the ranges in either order,
+/// and either bound of a range first.
+impl FromStr for Area2D {
+    type Err = ParseAreaError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let ranges = input.trim().strip_prefix(PREFIX).ok_or(ParseAreaError::MissingPrefix)?;
+
+        let mut x_bounds = None;
+        let mut y_bounds = None;
+        for range in ranges.split(',') {
+            let (axis, range) = range.split_once('=').ok_or_else(|| ParseAreaError::UnexpectedRange { text: range.trim().to_string() })?;
+            let bounds = match axis.trim() {
+                "x" => &mut x_bounds,
+                "y" => &mut y_bounds,
+                _ => return Err(ParseAreaError::UnexpectedRange { text: axis.trim().to_string() }),
+            };
+            let axis = axis.trim().chars().next().unwrap();
+            if bounds.is_some() {
+                return Err(ParseAreaError::DuplicateRange { axis })
+            }
+            *bounds = Some(parse_bounds(axis, range)?);
+        }
+
+        let (min_x, max_x) = x_bounds.ok_or(ParseAreaError::MissingRange { axis: 'x' })?;
+        let (min_y, max_y) = y_bounds.ok_or(ParseAreaError::MissingRange { axis: 'y' })?;
+        Ok(Area2D::from_bounds(min_x, max_x, min_y, max_y))
+    }
+}
+
+impl Display for Area2D {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} x={}..{}, y={}..{}", PREFIX, self.min_x(), self.max_x(), self.min_y(), self.max_y())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flexible_input_is_normalised() {
+        for input in [
+            "target area: x=20..30, y=-10..-5",
+            "target area: x=20..30, y=-10..-5\r\n",
+            "  target area:x = 20 .. 30 ,y= -10..-5  ",
+            "target area: x=30..20, y=-5..-10",
+            "target area: y=-10..-5, x=20..30",
+        ] {
+            assert_eq!(input.parse::<Area2D>().unwrap().to_string(), "target area: x=20..30, y=-10..-5", "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn errors_are_descriptive() {
+        let error = |input: &str| input.parse::<Area2D>().err().unwrap();
+        assert_eq!(error("area: x=20..30, y=-10..-5"), ParseAreaError::MissingPrefix);
+        assert_eq!(error("target area: x=20..30"), ParseAreaError::MissingRange { axis: 'y' });
+        assert_eq!(error("target area: x=20..30, -10..-5"), ParseAreaError::UnexpectedRange { text: "-10..-5".to_string() });
+        assert_eq!(error("target area: x=20..30, z=-10..-5"), ParseAreaError::UnexpectedRange { text: "z".to_string() });
+        assert_eq!(error("target area: x=20..30, x=1..2, y=1..2"), ParseAreaError::DuplicateRange { axis: 'x' });
+        assert_eq!(error("target area: x=20-30, y=-10..-5"), ParseAreaError::InvalidRange { axis: 'x', text: "20-30".to_string() });
+        assert!(matches!(error("target area: x=20..30, y=-10..five"), ParseAreaError::InvalidBound { axis: 'y', .. }));
+        assert_eq!(error("target area: x=20..30, y=..-5").to_string(), "invalid bound \"\" in the y= range: cannot parse integer from empty string");
+    }
+}
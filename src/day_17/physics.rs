@@ -0,0 +1,257 @@
+use std::cmp::max;
+use std::ops::RangeInclusive;
+
+use crate::array_2d::Coordinate;
+use super::Area2D;
+
+/// Rules the probe moves by. Per step, the position changes by the velocity, the x velocity is slowed
+/// towards 0 by `x_drag`, and the y velocity decreases by `gravity`, down to `-terminal_velocity` if set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Physics {
+    launch: Coordinate,
+    gravity: isize,
+    x_drag: isize,
+    terminal_velocity: Option<isize>,
+}
+
+/// The rules from the puzzle: launch from (0,0), unit drag and unit gravity.
+impl Default for Physics {
+    fn default() -> Self {
+        Physics { launch: Coordinate::from_coordinates(0, 0), gravity: 1, x_drag: 1, terminal_velocity: None }
+    }
+}
+
+#[allow(dead_code)]
+impl Physics {
+
+    pub fn with_launch(self, x: isize, y: isize) -> Self {
+        Physics { launch: Coordinate::from_coordinates(x, y), ..self }
+    }
+
+    pub fn with_gravity(self, gravity: isize) -> Self {
+        assert!(gravity >= 0, "Gravity pulling the probe upwards is not supported");
+        Physics { gravity, ..self }
+    }
+
+    pub fn with_x_drag(self, x_drag: isize) -> Self {
+        assert!(x_drag >= 0, "Drag speeding the probe up is not supported");
+        Physics { x_drag, ..self }
+    }
+
+    pub fn with_terminal_velocity(self, terminal_velocity: isize) -> Self {
+        assert!(terminal_velocity > 0, "Terminal velocity has to be positive");
+        Physics { terminal_velocity: Some(terminal_velocity), ..self }
+    }
+
+    pub fn launch(&self) -> Coordinate {
+        self.launch
+    }
+
+    pub fn gravity(&self) -> isize {
+        self.gravity
+    }
+
+    pub fn x_drag(&self) -> isize {
+        self.x_drag
+    }
+
+    pub fn terminal_velocity(&self) -> Option<isize> {
+        self.terminal_velocity
+    }
+
+    pub fn step(&self, position: &mut Coordinate, velocity: &mut Coordinate) {
+        position.increment_x(velocity.x());
+        position.increment_y(velocity.y());
+
+        let vx = velocity.x();
+        velocity.change_x(vx.signum() * max(vx.abs() - self.x_drag, 0));
+        let vy = velocity.y() - self.gravity;
+        velocity.change_y(match self.terminal_velocity {
+            Some(terminal_velocity) => max(vy, -terminal_velocity),
+            None => vy,
+        });
+    }
+
+    /// Highest y a probe launched at `velocity` reaches, or `None` if it keeps rising without gravity.
+    pub fn apex(&self, velocity: Coordinate) -> Option<isize> {
+        if self.gravity == 0 && velocity.y() > 0 {
+            return None
+        }
+        let mut position = self.launch;
+        let mut velocity = velocity;
+        while velocity.y() > 0 {
+            self.step(&mut position, &mut velocity);
+        }
+        Some(position.y())
+    }
+
+    /// Whether a probe at `position` moving at `velocity` can no longer end up in the area.
+    pub fn out_of_reach(&self, area: &Area2D, position: Coordinate, velocity: Coordinate) -> bool {
+        let (x, y, vx, vy) = (position.x(), position.y(), velocity.x(), velocity.y());
+        let x_out_of_reach = match vx {
+            0 => x < area.min_x() || area.max_x() < x,
+            vx if vx > 0 => area.max_x() < x,
+            _ => x < area.min_x(),
+        };
+        let y_out_of_reach = (y < area.min_y() && vy <= 0) || (self.gravity == 0 && area.max_y() < y && vy >= 0);
+        x_out_of_reach || y_out_of_reach
+    }
+}
+
+/// Distance a probe launched at `speed` travels along an axis slowed down by `drag` per step.
+fn stall_distance(speed: isize, drag: isize) -> isize {
+    let num_moves = (speed + drag - 1) / drag;
+    num_moves * speed - drag * num_moves * (num_moves - 1) / 2
+}
+
+/// Smallest speed at which the probe still travels `distance` before `drag` stalls it.
+fn min_speed_to_reach(distance: isize, drag: isize) -> isize {
+    if drag == 0 {
+        return 1
+    }
+    let (mut low, mut high) = (1, distance);
+    while low < high {
+        let mid = (low + high) / 2;
+        if stall_distance(mid, drag) >= distance { high = mid } else { low = mid + 1 }
+    }
+    low
+}
+
+/// Last step at which any of the x velocities is within the area, or `None` if one of them stalls within it.
+fn last_x_step(vx_range: RangeInclusive<isize>, area: &Area2D, x_drag: isize) -> Option<isize> {
+    let mut last_step = 0;
+    for mut vx in vx_range {
+        let (mut x, mut step) = (0, 0);
+        loop {
+            let within = area.min_x() <= x && x <= area.max_x();
+            if within && vx == 0 {
+                return None
+            }
+            if within {
+                last_step = max(last_step, step);
+            }
+            if vx == 0 || (vx > 0 && area.max_x() < x) || (vx < 0 && x < area.min_x()) {
+                break
+            }
+            x += vx;
+            vx = vx.signum() * max(vx.abs() - x_drag, 0);
+            step += 1;
+        }
+    }
+    Some(last_step)
+}
+
+/// Initial velocities outside of these ranges can never hit the area, or `None` if infinitely many of them do,
+/// which is the case when the terminal velocity lets a stalled probe fall through the area at any phase,
+/// and for targets touching the launcher's row, which probes launched upwards come back to.
+pub fn velocity_bounds(area: &Area2D, physics: &Physics) -> Option<(RangeInclusive<isize>, RangeInclusive<isize>)> {
+    let area = area.translated(-physics.launch.x(), -physics.launch.y());
+    if area.min_y() <= 0 && 0 <= area.max_y() {
+        return None
+    }
+    let gravity = physics.gravity;
+
+    // Must not overshoot in one step, and must not stall the x coordinate of the probe before reaching the near edge of the target
+    let vx_range = if area.min_x() > 0 {
+        min_speed_to_reach(area.min_x(), physics.x_drag)..=area.max_x()
+    } else if area.max_x() < 0 {
+        area.min_x()..=-min_speed_to_reach(-area.max_x(), physics.x_drag)
+    } else {
+        area.min_x()..=area.max_x()
+    };
+
+    // With a terminal velocity, only the number of steps the probe can spend within the target in x limits how high it may go.
+    // If it rises for all of these steps, it cannot hit the target in time
+    let max_steps = match physics.terminal_velocity {
+        Some(_) if gravity > 0 => Some(last_x_step(vx_range.clone(), &area, physics.x_drag)?),
+        _ => None,
+    };
+
+    let vy_range = if area.max_y() < 0 {
+        // Must not "overshoot" in one step, nor during the fall: the first position below the launcher's row is below -v_y / gravity
+        let max_vy = match max_steps {
+            Some(max_steps) => gravity * max_steps - 1,
+            None => gravity * -area.min_y() - 1,
+        };
+        area.min_y()..=max_vy
+    } else {
+        // Must reach the bottom of the target at the apex, and must not "overshoot" in the first step.
+        // Unit gravity revisits the heights of the way up on the way down, otherwise every position on the way down is above v_y / (2 * gravity)
+        let max_vy = match max_steps {
+            Some(max_steps) => max(area.max_y(), gravity * max_steps - 1),
+            None if gravity == 1 => area.max_y(),
+            None => max(area.max_y(), 2 * gravity * area.max_y() - 1),
+        };
+        min_speed_to_reach(area.min_y(), gravity)..=max_vy
+    };
+
+    Some((vx_range, vy_range))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_17::{hitting_velocities, simulate};
+
+    fn brute_force(area: &Area2D, physics: &Physics) -> usize {
+        let mut num_hits = 0;
+        for vx in -100..=100 {
+            for vy in -100..=100 {
+                num_hits += simulate(vx, vy, area, physics) as usize;
+            }
+        }
+        num_hits
+    }
+
+    #[test]
+    fn puzzle_rules_by_default() {
+        let area = Area2D::from_input("target area: x=20..30, y=-10..-5");
+        assert_eq!(hitting_velocities(&area, &Physics::default()).map(|velocities| velocities.len()), Some(112));
+        assert_eq!(min_speed_to_reach(20, 1), 6);
+        assert_eq!(min_speed_to_reach(21, 1), 6);
+        assert_eq!(min_speed_to_reach(22, 1), 7);
+        assert_eq!(min_speed_to_reach(22, 3), 10);
+    }
+
+    #[test]
+    fn bounds_contain_all_hits() {
+        let variants = [
+            Physics::default().with_gravity(2),
+            Physics::default().with_gravity(3).with_x_drag(2),
+            Physics::default().with_gravity(0).with_x_drag(0),
+            Physics::default().with_launch(5, -3).with_gravity(2).with_x_drag(0),
+            Physics::default().with_x_drag(0).with_terminal_velocity(3),
+            Physics::default().with_gravity(2).with_x_drag(3).with_terminal_velocity(4),
+        ];
+        for input in [
+            "target area: x=20..30, y=-10..-5",
+            "target area: x=-30..-20, y=5..10",
+            "target area: x=17..25, y=3..20",
+            "target area: x=-6..4, y=-10..-5",
+        ] {
+            let area = Area2D::from_input(input);
+            for physics in variants {
+                let Some(velocities) = hitting_velocities(&area, &physics) else { continue };
+                assert_eq!(velocities.len(), brute_force(&area, &physics), "{} {:?}", input, physics);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_gravity_apex() {
+        let physics = Physics::default().with_launch(0, 4).with_gravity(0);
+        assert_eq!(physics.apex(Coordinate::from_coordinates(3, 2)), None);
+        assert_eq!(physics.apex(Coordinate::from_coordinates(3, 0)), Some(4));
+        assert_eq!(physics.apex(Coordinate::from_coordinates(3, -2)), Some(4));
+        assert_eq!(Physics::default().apex(Coordinate::from_coordinates(3, 2)), Some(3));
+    }
+
+    #[test]
+    fn terminal_velocity_can_be_unbounded() {
+        // The probe stalls at x=21 and can then fall through the target at a rate of 3 per step from anywhere above it
+        let area = Area2D::from_input("target area: x=20..30, y=-20..-8");
+        let physics = Physics::default().with_terminal_velocity(3);
+        assert_eq!(hitting_velocities(&area, &physics), None);
+        assert!((0..100).all(|vy| simulate(6, vy, &area, &physics)));
+    }
+}
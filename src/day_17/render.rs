@@ -0,0 +1,103 @@
+use super::{Area2D, Trajectory};
+
+const MAX_WIDTH: usize = 160;
+const MAX_HEIGHT: usize = 80;
+
+#[allow(dead_code)]
+impl Trajectory {
+
+    /// Draws the trajectory like the puzzle statement: `S` for the launch point, `#` for the positions of the probe
+    /// up to its first hit, `T` for the target. Large scenes are scaled down to fit into 160x80 characters.
+    pub fn render(&self, target_area: &Area2D) -> String {
+        self.render_within(target_area, MAX_WIDTH, MAX_HEIGHT)
+    }
+
+    /// Like `render`, but scaled down until it fits into `max_width` x `max_height` characters, if needed.
+    /// Each character then covers a block of coordinates, drawn as the most important thing within the block.
+    pub fn render_within(&self, target_area: &Area2D, max_width: usize, max_height: usize) -> String {
+        let path = match self.first_hit {
+            Some(step) => &self.positions[..=step],
+            None => &self.positions[..],
+        };
+        let launch = path[0];
+
+        let min_x = path.iter().map(|position| position.x()).min().unwrap().min(target_area.min_x());
+        let max_x = path.iter().map(|position| position.x()).max().unwrap().max(target_area.max_x());
+        let min_y = path.iter().map(|position| position.y()).min().unwrap().min(target_area.min_y());
+        let max_y = path.iter().map(|position| position.y()).max().unwrap().max(target_area.max_y());
+
+        let width = (max_x - min_x + 1) as usize;
+        let height = (max_y - min_y + 1) as usize;
+        let cell_width = width.div_ceil(max_width.max(1));
+        let cell_height = height.div_ceil(max_height.max(1));
+        let col = |x: isize| (x - min_x) as usize / cell_width;
+        let row = |y: isize| (max_y - y) as usize / cell_height;
+
+        let mut canvas = vec![vec!['.'; width.div_ceil(cell_width)]; height.div_ceil(cell_height)];
+        for line in &mut canvas[row(target_area.max_y())..=row(target_area.min_y())] {
+            line[col(target_area.min_x())..=col(target_area.max_x())].fill('T');
+        }
+        for position in &path[1..] {
+            canvas[row(position.y())][col(position.x())] = '#';
+        }
+        canvas[row(launch.y())][col(launch.x())] = 'S';
+
+        canvas.iter().map(|line| line.iter().collect::<String>()).collect::<Vec<String>>().join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day_17::{Area2D, Physics, Trajectory};
+
+    #[test]
+    fn aoc_example_works() {
+        let area = Area2D::from_input("target area: x=20..30, y=-10..-5");
+        let expected = "\
+.............#....#............
+.......#..............#........
+...............................
+S........................#.....
+...............................
+...............................
+...........................#...
+...............................
+....................TTTTTTTTTTT
+....................TTTTTTTTTTT
+....................TTTTTTTT#TT
+....................TTTTTTTTTTT
+....................TTTTTTTTTTT
+....................TTTTTTTTTTT";
+        assert_eq!(Trajectory::from_launch(7, 2, &area, &Physics::default()).render(&area), expected);
+    }
+
+    #[test]
+    fn misses_show_the_whole_path() {
+        // Ends with the first position from which the target is out of reach
+        let area = Area2D::from_input("target area: x=20..30, y=-10..-5");
+        let expected = "\
+S.................................
+..................................
+..................................
+..................................
+.................#................
+....................TTTTTTTTTTT...
+....................TTTTTTTTTTT...
+....................TTTTTTTTTTT...
+....................TTTTTTTTTTT...
+....................TTTTTTTTTTT..#
+....................TTTTTTTTTTT...";
+        assert_eq!(Trajectory::from_launch(17, -4, &area, &Physics::default()).render(&area), expected);
+    }
+
+    #[test]
+    fn large_scenes_are_scaled_down() {
+        let area = Area2D::from_input("target area: x=257..286, y=-101..-57");
+        let picture = Trajectory::from_launch(23, 100, &area, &Physics::default()).render_within(&area, 40, 20);
+        let lines: Vec<&str> = picture.lines().collect();
+        assert!(lines.len() <= 20 && lines.iter().all(|line| line.len() <= 40));
+        assert!(lines.iter().all(|line| line.len() == lines[0].len()));
+        assert!(lines.iter().any(|line| line.starts_with('S')));
+        assert!(picture.contains('#') && picture.contains('T'));
+    }
+}
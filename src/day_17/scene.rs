@@ -0,0 +1,190 @@
+use std::cmp::Reverse;
+
+use crate::array_2d::Coordinate;
+use super::{velocity_bounds, Area2D, Physics};
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ranking {
+    HighestApex,
+    FewestSteps,
+}
+
+/// An initial velocity completing a route, with the step at which it reaches the last target of the route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shot {
+    velocity: Coordinate,
+    apex: Option<isize>,
+    steps: usize,
+}
+
+#[allow(dead_code)]
+impl Shot {
+    pub fn velocity(&self) -> Coordinate {
+        self.velocity
+    }
+
+    /// `None` if the probe keeps rising, see `Physics::apex`.
+    pub fn apex(&self) -> Option<isize> {
+        self.apex
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+}
+
+/// Several targets and obstacles under the same physics. The probe must not be within any obstacle at any step
+/// after its launch, up to the step it completes its route.
+pub struct Scene {
+    physics: Physics,
+    targets: Vec<Area2D>,
+    obstacles: Vec<Area2D>,
+}
+
+#[allow(dead_code)]
+impl Scene {
+
+    pub fn from_physics(physics: Physics) -> Self {
+        Scene { physics, targets: Vec::new(), obstacles: Vec::new() }
+    }
+
+    /// Adds a target and returns the index to refer to it by in routes.
+    pub fn add_target(&mut self, area: Area2D) -> usize {
+        self.targets.push(area);
+        self.targets.len() - 1
+    }
+
+    pub fn add_obstacle(&mut self, area: Area2D) {
+        self.obstacles.push(area);
+    }
+
+    /// Step at which a probe launched at `(vx, vy)` has been within all targets of the `route` in order,
+    /// each at a later step than the previous one.
+    fn completion_step(&self, vx: isize, vy: isize, route: &[usize]) -> Option<usize> {
+        let mut position = self.physics.launch();
+        let mut velocity = Coordinate::from_coordinates(vx, vy);
+        let mut num_visited = 0;
+        for step in 0.. {
+            if step > 0 && self.obstacles.iter().any(|obstacle| obstacle.coordinates_within(position.x(), position.y())) {
+                return None
+            }
+            let target = &self.targets[route[num_visited]];
+            if target.coordinates_within(position.x(), position.y()) {
+                num_visited += 1;
+                if num_visited == route.len() {
+                    return Some(step)
+                }
+            } else if self.physics.out_of_reach(target, position, velocity) {
+                return None
+            }
+            self.physics.step(&mut position, &mut velocity);
+        }
+        unreachable!()
+    }
+
+    /// All shots passing through the targets of the `route` in order without touching an obstacle, best first,
+    /// or `None` if there are infinitely many candidates.
+    pub fn shots(&self, route: &[usize], ranking: Ranking) -> Option<Vec<Shot>> {
+        assert!(!route.is_empty(), "A route needs at least one target");
+        // Any shot completing the route hits its last target, so it is within the bounds of that one
+        let (vx_range, vy_range) = velocity_bounds(&self.targets[*route.last().unwrap()], &self.physics)?;
+
+        let mut shots = Vec::new();
+        for vx in vx_range {
+            for vy in vy_range.clone() {
+                if let Some(steps) = self.completion_step(vx, vy, route) {
+                    let velocity = Coordinate::from_coordinates(vx, vy);
+                    shots.push(Shot { velocity, apex: self.physics.apex(velocity), steps });
+                }
+            }
+        }
+
+        // Probes rising forever rank above all others
+        let height_order = |shot: &Shot| Reverse(shot.apex.unwrap_or(isize::MAX));
+        let velocity_order = |shot: &Shot| (shot.velocity.x(), shot.velocity.y());
+        match ranking {
+            Ranking::HighestApex => shots.sort_by_key(|shot| (height_order(shot), shot.steps, velocity_order(shot))),
+            Ranking::FewestSteps => shots.sort_by_key(|shot| (shot.steps, height_order(shot), velocity_order(shot))),
+        }
+        Some(shots)
+    }
+
+    pub fn best_shot(&self, route: &[usize], ranking: Ranking) -> Option<Shot> {
+        self.shots(route, ranking)?.first().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_17::{hitting_velocities, part_1, part_2, Trajectory};
+
+    const EXAMPLE: &str = "target area: x=20..30, y=-10..-5";
+
+    #[test]
+    fn single_target_matches_puzzle() {
+        let mut scene = Scene::from_physics(Physics::default());
+        let target = scene.add_target(Area2D::from_input(EXAMPLE));
+        let shots = scene.shots(&[target], Ranking::HighestApex).unwrap();
+        assert_eq!(shots.len() as isize, part_2(EXAMPLE));
+        assert_eq!(shots[0].apex(), Some(part_1(EXAMPLE)));
+
+        // Shooting straight into the target is the fastest way, ties are broken by velocity
+        let fastest = scene.best_shot(&[target], Ranking::FewestSteps).unwrap();
+        assert_eq!(fastest.steps(), 1);
+        assert_eq!(fastest.velocity(), Coordinate::from_coordinates(20, -10));
+    }
+
+    #[test]
+    fn obstacles_block_shots() {
+        let area = Area2D::from_input(EXAMPLE);
+        let ceiling = Area2D::from_input("target area: x=-100..100, y=30..40");
+        let mut scene = Scene::from_physics(Physics::default());
+        let target = scene.add_target(Area2D::from_input(EXAMPLE));
+        scene.add_obstacle(Area2D::from_input("target area: x=-100..100, y=30..40"));
+
+        let num_unblocked = hitting_velocities(&area, &Physics::default()).unwrap().iter().filter(|velocity| {
+            let trajectory = Trajectory::from_launch(velocity.x(), velocity.y(), &area, &Physics::default());
+            let path = &trajectory.positions()[1..=trajectory.first_hit().unwrap()];
+            path.iter().all(|position| !ceiling.coordinates_within(position.x(), position.y()))
+        }).count();
+        let shots = scene.shots(&[target], Ranking::HighestApex).unwrap();
+        assert_eq!(shots.len(), num_unblocked);
+        assert!(shots.len() < 112);
+        assert!(shots[0].apex() < Some(30));
+    }
+
+    #[test]
+    fn zero_gravity_shots_rise_forever() {
+        let physics = Physics::default().with_gravity(0);
+        let mut scene = Scene::from_physics(physics);
+        let target = scene.add_target(Area2D::from_input("target area: x=20..30, y=5..10"));
+
+        let shots = scene.shots(&[target], Ranking::HighestApex).unwrap();
+        assert!(!shots.is_empty());
+        assert_eq!(shots.len(), hitting_velocities(&Area2D::from_input("target area: x=20..30, y=5..10"), &physics).unwrap().len());
+        assert!(shots.iter().all(|shot| shot.apex().is_none()));
+        assert!(shots.windows(2).all(|pair| pair[0].steps() <= pair[1].steps()));
+    }
+
+    #[test]
+    fn routes_are_followed_in_order() {
+        let mut scene = Scene::from_physics(Physics::default());
+        let high = scene.add_target(Area2D::from_input("target area: x=5..10, y=1..3"));
+        let low = scene.add_target(Area2D::from_input(EXAMPLE));
+
+        let shots = scene.shots(&[high, low], Ranking::FewestSteps).unwrap();
+        assert!(!shots.is_empty());
+        assert!(shots.windows(2).all(|pair| pair[0].steps() <= pair[1].steps()));
+        for shot in &shots {
+            let trajectory = Trajectory::from_launch(shot.velocity().x(), shot.velocity().y(), &Area2D::from_input(EXAMPLE), &Physics::default());
+            assert_eq!(trajectory.first_hit(), Some(shot.steps()));
+            let high_area = Area2D::from_input("target area: x=5..10, y=1..3");
+            assert!(trajectory.positions()[..shot.steps()].iter().any(|position| high_area.coordinates_within(position.x(), position.y())));
+        }
+
+        // Once below the launcher, the probe cannot rise to the other target anymore
+        assert_eq!(scene.shots(&[low, high], Ranking::FewestSteps), Some(Vec::new()));
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::analytic::{state_at, x_window};
+use super::parse::ParseAreaError;
+use super::{velocity_bounds, Area2D, Physics};
+use crate::work_queue::fold_parallel;
+
+#[derive(Debug)]
+pub enum SweepError {
+    MissingInput,
+    Unreadable { file: String, error: std::io::Error },
+    InvalidArea { file: String, error: ParseAreaError },
+    InvalidThreads { text: String },
+    Unbounded { file: String },
+}
+
+impl Display for SweepError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SweepError::MissingInput => write!(f, "usage: trick-shot-sweep <input file> [threads]"),
+            SweepError::Unreadable { file, error } => write!(f, "{}: cannot read the target area: {}", file, error),
+            SweepError::InvalidArea { file, error } => write!(f, "{}: {}", file, error),
+            SweepError::InvalidThreads { text } => write!(f, "expected a positive number of threads but got {:?}", text),
+            SweepError::Unbounded { file } => write!(f, "{}: {}", file, super::UNBOUNDED_TARGET),
+        }
+    }
+}
+
+impl std::error::Error for SweepError {}
+
+/// Called with the number of finished `vx` columns and the total number of columns.
+pub type Progress<'a> = &'a (dyn Fn(usize, usize) + Sync);
+
+/// Counts the hitting velocities of the column `vx` under the puzzle rules. The x step window tells if the column
+/// can hit at all, and lets each simulation start right at the first step the probe is within the target in x.
+fn count_column(vx: isize, vy_range: impl Iterator<Item = isize>, area: &Area2D, physics: &Physics) -> usize {
+    let Some(window) = x_window(vx, area) else { return 0 };
+    let mut num_hits = 0;
+    for vy in vy_range {
+        let (mut position, mut velocity) = state_at(vx, vy, window.first as isize);
+        while !physics.out_of_reach(area, position, velocity) {
+            if area.coordinates_within(position.x(), position.y()) {
+                num_hits += 1;
+                break
+            }
+            physics.step(&mut position, &mut velocity);
+        }
+    }
+    num_hits
+}
+
+/// Brute-force count of the hitting velocities, sweeping the `vx` columns of the velocity grid on `num_threads`
+/// threads. `None` if infinitely many velocities hit.
+pub fn count_hits_parallel(area: &Area2D, num_threads: usize, progress: Option<Progress>) -> Option<usize> {
+    let physics = Physics::default();
+    let (vx_range, vy_range) = velocity_bounds(area, &physics)?;
+    let columns: Vec<isize> = vx_range.collect();
+    let num_finished = AtomicUsize::new(0);
+
+    let num_hits = fold_parallel(&columns, num_threads, || 0, |num_hits, _, &vx| {
+        *num_hits += count_column(vx, vy_range.clone(), area, &physics);
+        let finished = num_finished.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(progress) = progress {
+            progress(finished, columns.len());
+        }
+    });
+    Some(num_hits.into_iter().sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::day_17::part_2;
+
+    #[test]
+    fn matches_sequential_sweep() {
+        for input in [
+            "target area: x=20..30, y=-10..-5",
+            "target area: x=257..286, y=-101..-57",
+            "target area: x=-30..-20, y=5..10",
+            "target area: x=-6..4, y=-10..-5",
+        ] {
+            for num_threads in [1, 3, 8] {
+                assert_eq!(count_hits_parallel(&Area2D::from_input(input), num_threads, None).map(|num_hits| num_hits as isize), Some(part_2(input)), "{}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn progress_is_reported_per_column() {
+        let reports = Mutex::new(Vec::new());
+        let record = |finished, total| reports.lock().unwrap().push((finished, total));
+        count_hits_parallel(&Area2D::from_input("target area: x=20..30, y=-10..-5"), 4, Some(&record));
+
+        let mut reports = reports.into_inner().unwrap();
+        reports.sort_unstable();
+        // Columns vx=6..=30
+        assert_eq!(reports, (1..=25).map(|finished| (finished, 25)).collect::<Vec<_>>());
+    }
+}
@@ -1,8 +1,30 @@
-use std::cmp::max;
-use std::ops::Add;
-use std::str::Chars;
+use std::cmp::{max, Ordering};
+use std::fmt::{Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
 
-#[derive(Debug, Clone)]
+mod arena;
+mod flat;
+#[cfg(test)]
+mod generator;
+mod homework;
+mod parse;
+#[cfg(test)]
+mod reference;
+mod rules;
+mod search;
+mod trace;
+mod traversal;
+
+use arena::ArenaSnailfishNumber;
+use flat::FlatSnailfishNumber;
+use homework::{HomeworkError, Report};
+use rules::ReductionRules;
+use search::PairSum;
+use trace::{ReductionAction, ReductionStep, Side};
+use traversal::Token;
+
+/// Deeply nested numbers get cloned, compared, hashed and dropped without recursion, see `traversal`.
 struct SnailfishNumberNode{
     regular_number_value: Option<usize>,
     left: Option<Box<SnailfishNumberNode>>,
@@ -21,124 +43,87 @@ impl SnailfishNumberNode {
     }
 
     fn node_with_children(left: Box<SnailfishNumberNode>, right: Box<SnailfishNumberNode>) -> Self {
-        let new_node = Self {
+        Self {
             height: max(left.height, right.height) + 1,
             regular_number_value: None,
             left: Some(left),
             right: Some(right),
-        };
-        new_node
-    }
-
-    fn magnitude(&self) -> usize {
-        if let Some(value) = self.regular_number_value {
-            value
-        }
-        else {
-            3 * self.left.as_ref().unwrap().magnitude() + 2 * self.right.as_ref().unwrap().magnitude()
         }
     }
 
-    fn is_leaf(&self) -> bool {
-        self.regular_number_value.is_some()
+    /// Saturates at `usize::MAX`, which deeply nested numbers easily reach.
+    fn magnitude(&self, rules: &ReductionRules) -> usize {
+        let (left_weight, right_weight) = rules.magnitude_weights;
+        let mut magnitudes = Vec::new();
+        for token in self.tokens() {
+            match token {
+                Token::Open => {},
+                Token::Regular(value) => magnitudes.push(value),
+                Token::Close => {
+                    let right = magnitudes.pop().unwrap();
+                    let left = magnitudes.pop().unwrap();
+                    magnitudes.push(left_weight.saturating_mul(left).saturating_add(right_weight.saturating_mul(right)));
+                },
+            }
+        }
+        magnitudes.pop().unwrap()
     }
 
     fn get_leftmost_leaf(&mut self) -> &mut SnailfishNumberNode {
-        if self.left.is_some() {
-            self.left.as_mut().unwrap().get_leftmost_leaf()
-        } else {
-            self
+        let mut node = self;
+        while node.left.is_some() {
+            node = node.left.as_mut().unwrap();
         }
+        node
     }
 
     fn get_rightmost_leaf(&mut self) -> &mut SnailfishNumberNode {
-        if self.right.is_some() {
-            self.right.as_mut().unwrap().get_rightmost_leaf()
-        } else {
-            self
+        let mut node = self;
+        while node.right.is_some() {
+            node = node.right.as_mut().unwrap();
         }
+        node
     }
 
+    fn child_mut(&mut self, side: Side) -> &mut SnailfishNumberNode {
+        match side {
+            Side::Left => self.left.as_mut().unwrap(),
+            Side::Right => self.right.as_mut().unwrap(),
+        }
+    }
+
+    /// Saturates at `usize::MAX`, which regular numbers can already start out at.
     fn increase_regular_value(&mut self, to_add: usize) {
         if let Some(regular_value) = self.regular_number_value.as_mut() {
-            *regular_value += to_add;
+            *regular_value = regular_value.saturating_add(to_add);
         }
     }
 
-    fn split(&mut self) -> bool {
-        if let Some(val) = self.regular_number_value {
-            if val >= 10 {
-                self.left = Some(Box::new(SnailfishNumberNode::leaf_from_regular_value(self.regular_number_value.unwrap() / 2)));
-                self.right = Some(Box::new(SnailfishNumberNode::leaf_from_regular_value((self.regular_number_value.unwrap() + 1) / 2)));
-                self.regular_number_value = None;
-                self.height = 1;
-                return true
-            }
-        } else {
-            if let Some(left) = self.left.as_mut() {
-                if left.split() {
-                    self.height = max(left.height + 1, self.height);
-                    return true
-                }
-            }
-            if let Some(right) = self.right.as_mut() {
-                if right.split() {
-                    self.height = max(right.height + 1, self.height);
-                    return true
-                }
-            }
-        }
-        false
-    }
-
-    fn explode(&mut self) -> (bool, usize, bool, usize) {
-        if self.height == 1 {
-            let left_value = self.left.as_ref().unwrap().regular_number_value.unwrap();
-            let right_value = self.right.as_ref().unwrap().regular_number_value.unwrap();
-            self.regular_number_value = Some(0);
-            self.left = None;
-            self.right = None;
-            self.height = 0;
-            // println!("TADA VALUES TO EXPLODE: {left_value}, {right_value}");
-            return (false, left_value, false, right_value)
-        }
-        let left = self.left.as_mut().unwrap();
-        let right = self.right.as_mut().unwrap();
-        if left.height == self.height - 1 {
-            let (left_assigned, left_value, mut right_assigned, right_value) = left.explode();
-            if !right_assigned {
-                let leftmost_in_right_subtree = right.get_leftmost_leaf();
-                leftmost_in_right_subtree.increase_regular_value(right_value);
-                right_assigned = true;
-            }
-            self.height = max(left.height, right.height) + 1;
-            // println!("Recursion values: {left_assigned}, {left_value}, {right_assigned}, {right_value}");
-            (left_assigned, left_value, right_assigned, right_value)
-        } else {
-            let (mut left_assigned, left_value, right_assigned, right_value) = right.explode();
-            if !left_assigned {
-                let rightmost_in_left_subtree = left.get_rightmost_leaf();
-                rightmost_in_left_subtree.increase_regular_value(left_value);
-                left_assigned = true;
-            }
-            self.height = max(left.height, right.height) + 1;
-            // println!("Recursion values: {left_assigned}, {left_value}, {right_assigned}, {right_value}");
-            (left_assigned, left_value, right_assigned, right_value)
-        }
+    fn is_regular_pair(&self) -> bool {
+        let is_leaf = |child: &Option<Box<SnailfishNumberNode>>| child.as_ref().is_some_and(|child| child.regular_number_value.is_some());
+        is_leaf(&self.left) && is_leaf(&self.right)
     }
+}
 
-    fn to_string(&self) -> String {
-        if let Some(value) = self.regular_number_value {
-            value.to_string()
-        } else {
-            let left_str = self.left.as_ref().unwrap().to_string();
-            let right_str = self.right.as_ref().unwrap().to_string();
-            format!("[{},{}]", left_str, right_str)
+impl Display for SnailfishNumberNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut follows_element = false;
+        for token in self.tokens() {
+            if follows_element && token != Token::Close {
+                write!(f, ",")?;
+            }
+            match token {
+                Token::Open => write!(f, "[")?,
+                Token::Regular(value) => write!(f, "{}", value)?,
+                Token::Close => write!(f, "]")?,
+            }
+            follows_element = token != Token::Open;
         }
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct SnailfishNumberTree {
     root: Box<SnailfishNumberNode>,
 }
@@ -149,63 +134,161 @@ impl SnailfishNumberTree {
     }
 
     fn from_input(input: &str) -> Self {
-        let mut chars = input.chars();
-        let root = SnailfishNumberTree::parse_node(&mut chars);
-        SnailfishNumberTree { root }
-    }
-
-    fn parse_node(chars: &mut Chars) -> Box<SnailfishNumberNode> {
-        let current_char = chars.next();
-
-        match current_char {
-            Some('[') => {
-                let left = SnailfishNumberTree::parse_node(chars);
-                assert_eq!(chars.next(), Some(','));
-                let right = SnailfishNumberTree::parse_node(chars);
-                assert_eq!(chars.next(), Some(']'));
-                let node = Box::new(SnailfishNumberNode::node_with_children(left, right));
-                node
-            }
-            Some(digit) if digit.is_digit(10) => {
-                let mut num_str = digit.to_string();
-                while let Some(c) = chars.clone().next() {
-                    if c.is_digit(10) {
-                        num_str.push(c);
-                        chars.next();
-                    } else {
-                        break;
-                    }
-                }
-                let number = num_str.parse::<usize>().unwrap();
-                Box::new(SnailfishNumberNode::leaf_from_regular_value(number))
-            }
-            _ => panic!("Unexpected char in SnailfishNumberTree"),
-        }
+        input.parse().unwrap_or_else(|error| panic!("Invalid snailfish number: {}", error))
     }
 
     fn magnitude(&self) -> usize {
-        self.root.magnitude()
+        self.root.magnitude(&ReductionRules::default())
+    }
+
+    #[allow(dead_code)]
+    fn magnitude_with(&self, rules: &ReductionRules) -> usize {
+        self.root.magnitude(rules)
+    }
+
+    /// The pair `[self,rhs]`, not reduced yet.
+    fn pair_with(self, rhs: SnailfishNumberTree) -> Self {
+        let res_root = SnailfishNumberNode  {
+            regular_number_value: None,
+            height: max(self.root.height, rhs.root.height) + 1,
+            left: Some(self.root),
+            right: Some(rhs.root),
+        };
+        SnailfishNumberTree::from_root(res_root)
     }
 
     fn reduce(&mut self) {
-        loop {
-            if self.root.height >= 5 {
-                self.root.explode();
-                // println!("After explode: {}", self.to_string());
-                // println!("Root height: {}", self.root.height);
-                continue
+        self.reduce_recording(&ReductionRules::default(), None);
+    }
+
+    /// Overwrites `self` with the sum of `lhs` and `rhs`, reusing its nodes so that repeated sums allocate little.
+    fn assign_sum(&mut self, lhs: &SnailfishNumberTree, rhs: &SnailfishNumberTree) {
+        let root = &mut *self.root;
+        for (child, operand) in [(&mut root.left, lhs), (&mut root.right, rhs)] {
+            match child {
+                Some(child) => child.clone_from(&operand.root),
+                None => *child = Some(operand.root.clone()),
+            }
+        }
+        root.regular_number_value = None;
+        root.height = max(lhs.root.height, rhs.root.height) + 1;
+        self.reduce();
+    }
+
+    #[allow(dead_code)]
+    fn reduce_with(&mut self, rules: &ReductionRules) {
+        self.reduce_recording(rules, None);
+    }
+
+    /// Adds like `+`, but under the given rules.
+    #[allow(dead_code)]
+    fn add_with(self, rhs: SnailfishNumberTree, rules: &ReductionRules) -> Self {
+        let mut res = self.pair_with(rhs);
+        res.reduce_with(rules);
+        res
+    }
+
+    /// Explodes the leftmost pair of two regular numbers nested inside at least `max_depth` pairs.
+    fn explode(&mut self, max_depth: usize) -> Option<ReductionAction> {
+        // The pairs within a node nested inside `depth` pairs are nested inside depth..depth + height pairs. Its deepest
+        // pair is a pair of two regular numbers, so the leftmost child that goes deep enough leads to the pair to explode
+        let reaches_max_depth = |node: &SnailfishNumberNode, depth: usize| node.height > 0 && depth + node.height > max_depth;
+        if !reaches_max_depth(&self.root, 0) {
+            return None
+        }
+        let mut path = Vec::with_capacity(self.root.height);
+        let mut heights = Vec::with_capacity(self.root.height);
+        let mut node = &*self.root;
+        while path.len() < max_depth || !node.is_regular_pair() {
+            let (left, right) = (node.left.as_ref().unwrap(), node.right.as_ref().unwrap());
+            if reaches_max_depth(left, path.len() + 1) {
+                path.push(Side::Left);
+                heights.push(right.height);
+                node = left;
+            } else {
+                path.push(Side::Right);
+                heights.push(left.height);
+                node = right;
             }
-            if self.root.split() {
-                // println!("After split: {}", self.to_string());
-                // println!("Root height: {}", self.root.height);
-                continue
+        }
+        let left = node.left.as_ref().unwrap().regular_number_value.unwrap();
+        let right = node.right.as_ref().unwrap().regular_number_value.unwrap();
+
+        // Only the heights along the path change, each depending on the height of the child off the path
+        let mut height = 0;
+        for sibling_height in heights.iter_mut().rev() {
+            height = max(height, *sibling_height) + 1;
+            *sibling_height = height;
+        }
+        // The closest regular number to the left is the rightmost one in the left child of the last pair the path goes
+        // right at, and the other way around
+        let left_turn = path.iter().rposition(|&side| side == Side::Right);
+        let right_turn = path.iter().rposition(|&side| side == Side::Left);
+
+        let mut node = &mut *self.root;
+        for (i, &side) in path.iter().enumerate() {
+            node.height = heights[i];
+            if left_turn == Some(i) {
+                node.left.as_mut().unwrap().get_rightmost_leaf().increase_regular_value(left);
+            }
+            if right_turn == Some(i) {
+                node.right.as_mut().unwrap().get_leftmost_leaf().increase_regular_value(right);
+            }
+            node = node.child_mut(side);
+        }
+        node.left = None;
+        node.right = None;
+        node.regular_number_value = Some(0);
+        node.height = 0;
+        Some(ReductionAction::Explode { path, left, right, left_moved: left_turn.is_some(), right_moved: right_turn.is_some() })
+    }
+
+    /// Splits the leftmost regular number of at least the split threshold.
+    fn split(&mut self, rules: &ReductionRules) -> Option<ReductionAction> {
+        let path = self.root.find_path(|node| node.regular_number_value.is_some_and(|value| value >= rules.split_threshold))?;
+        let mut node = &mut *self.root;
+        for (i, &side) in path.iter().enumerate() {
+            // The new pair at the end of the path is one level higher than the number it replaces
+            node.height = max(node.height, path.len() - i + 1);
+            node = node.child_mut(side);
+        }
+        let value = node.regular_number_value.take().unwrap();
+        let (left_half, right_half) = rules.split_halves(value);
+        node.left = Some(Box::new(SnailfishNumberNode::leaf_from_regular_value(left_half)));
+        node.right = Some(Box::new(SnailfishNumberNode::leaf_from_regular_value(right_half)));
+        node.height = 1;
+        Some(ReductionAction::Split { path, value })
+    }
+
+    /// Reduces the number, appending every action taken to `steps` if given.
+    fn reduce_recording(&mut self, rules: &ReductionRules, mut steps: Option<&mut Vec<ReductionStep>>) {
+        // Zero would split into zeros forever
+        assert!(rules.split_threshold > 0, "Split threshold has to be positive");
+        while let Some(action) = self.explode(rules.max_depth).or_else(|| self.split(rules)) {
+            if let Some(steps) = steps.as_mut() {
+                steps.push(ReductionStep::from_action(action, self));
             }
-            break
         }
     }
 
-    fn to_string(&self) -> String {
-        self.root.to_string()
+}
+
+impl Display for SnailfishNumberTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.root)
+    }
+}
+
+/// Orders by magnitude first, then by structure so that only equal numbers compare as equal.
+impl Ord for SnailfishNumberTree {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.magnitude().cmp(&other.magnitude()).then_with(|| self.root.cmp(&other.root))
+    }
+}
+
+impl PartialOrd for SnailfishNumberTree {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -213,53 +296,117 @@ impl Add<SnailfishNumberTree> for SnailfishNumberTree {
     type Output = SnailfishNumberTree;
 
     fn add(self, rhs: SnailfishNumberTree) -> Self::Output {
-        let res_root = SnailfishNumberNode  {
-            regular_number_value: None,
-            height: max(self.root.height, rhs.root.height) + 1,
-            left: Some(Box::new(*self.root)),
-            right: Some(Box::new(*rhs.root)),
-        };
-
-        let mut res = SnailfishNumberTree::from_root(res_root);
-        // println!("After addition: {}", res.to_string());
-        // println!("Root height: {}", res.root.height);
+        let mut res = self.pair_with(rhs);
         res.reduce();
         res
     }
 }
 
+impl Add<&SnailfishNumberTree> for SnailfishNumberTree {
+    type Output = SnailfishNumberTree;
+
+    fn add(self, rhs: &SnailfishNumberTree) -> Self::Output {
+        self + rhs.clone()
+    }
+}
+
+impl Add<&SnailfishNumberTree> for &SnailfishNumberTree {
+    type Output = SnailfishNumberTree;
+
+    fn add(self, rhs: &SnailfishNumberTree) -> Self::Output {
+        self.clone() + rhs.clone()
+    }
+}
+
+impl AddAssign<SnailfishNumberTree> for SnailfishNumberTree {
+    fn add_assign(&mut self, rhs: SnailfishNumberTree) {
+        let placeholder = SnailfishNumberTree::from_root(SnailfishNumberNode::leaf_from_regular_value(0));
+        *self = std::mem::replace(self, placeholder) + rhs;
+    }
+}
+
+impl AddAssign<&SnailfishNumberTree> for SnailfishNumberTree {
+    fn add_assign(&mut self, rhs: &SnailfishNumberTree) {
+        *self += rhs.clone();
+    }
+}
+
+/// Panics on an empty iterator, as there is no snailfish number that leaves others unchanged when added.
+impl Sum for SnailfishNumberTree {
+    fn sum<I: Iterator<Item = SnailfishNumberTree>>(iter: I) -> Self {
+        iter.reduce(|sum, summand| sum + summand).expect("Cannot sum zero snailfish numbers")
+    }
+}
+
 pub fn part_1(input: &str) -> usize {
-    let mut summands = input.lines();
-    let mut sum = SnailfishNumberTree::from_input(summands.next().unwrap());
-    for line in summands {
-        // println!("{}", line);
-        // println!("{}", sum.to_string());
-        // println!("{:?}", SnailfishNumberTree::from_input(&line).to_string());
-        sum = sum + SnailfishNumberTree::from_input(&line);
-        // println!("{:?}", sum.to_string());
-        // println!("{:?}", sum.magnitude());
-        // println!()
-    }
-    sum.magnitude()
+    input.lines().map(SnailfishNumberTree::from_input).sum::<SnailfishNumberTree>().magnitude()
 }
 
-pub fn part_2(input: &str) -> usize {
+/// Largest magnitude of the sum of two different numbers, in either order, as computed by `sum_magnitude`.
+fn max_pair_magnitude<T>(numbers: &[T], mut sum_magnitude: impl FnMut(&T, &T) -> usize) -> usize {
     let mut max_magnitude = 0;
-    let snailfish_numbers: Vec<SnailfishNumberTree> = input.lines().map(|line| SnailfishNumberTree::from_input(line)).collect();
-    for i in 0..snailfish_numbers.len() {
-        for j in 0..snailfish_numbers.len() {
-            let sum = snailfish_numbers[i].clone() + snailfish_numbers[j].clone();
-            max_magnitude = max(max_magnitude, sum.magnitude());
+    for (i, a) in numbers.iter().enumerate() {
+        for (j, b) in numbers.iter().enumerate() {
+            if i != j {
+                max_magnitude = max(max_magnitude, sum_magnitude(a, b));
+            }
         }
     }
     max_magnitude
 }
 
+pub fn part_2(input: &str) -> usize {
+    let snailfish_numbers: Vec<SnailfishNumberTree> = input.lines().map(SnailfishNumberTree::from_input).collect();
+    let mut sum = SnailfishNumberTree::from_root(SnailfishNumberNode::leaf_from_regular_value(0));
+    max_pair_magnitude(&snailfish_numbers, |a, b| {
+        sum.assign_sum(a, b);
+        sum.magnitude()
+    })
+}
+
+/// Adds up the numbers of each problem in the homework files, printing every running sum and its magnitude.
+pub fn print_homework(paths: &[String]) -> Result<(), HomeworkError> {
+    let problems = homework::read_homework(paths)?;
+    print!("{}", Report(&problems));
+    Ok(())
+}
+
+pub fn part_2_parallel(input: &str) -> usize {
+    let snailfish_numbers: Vec<FlatSnailfishNumber> = input.lines().map(FlatSnailfishNumber::from_input).collect();
+    let num_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    search::top_pairs(&snailfish_numbers, 1, num_threads).first().map_or(0, PairSum::magnitude)
+}
+
+pub fn part_1_flat(input: &str) -> usize {
+    let mut summands = input.lines().map(FlatSnailfishNumber::from_input);
+    let first = summands.next().unwrap();
+    summands.fold(first, |sum, summand| &sum + &summand).magnitude()
+}
+
+pub fn part_2_flat(input: &str) -> usize {
+    let snailfish_numbers: Vec<FlatSnailfishNumber> = input.lines().map(FlatSnailfishNumber::from_input).collect();
+    max_pair_magnitude(&snailfish_numbers, |a, b| (a + b).magnitude())
+}
+
+
+pub fn part_1_arena(input: &str) -> usize {
+    let mut summands = input.lines().map(ArenaSnailfishNumber::from_input);
+    let first = summands.next().unwrap();
+    summands.fold(first, |sum, summand| &sum + &summand).magnitude()
+}
+
+pub fn part_2_arena(input: &str) -> usize {
+    let snailfish_numbers: Vec<ArenaSnailfishNumber> = input.lines().map(ArenaSnailfishNumber::from_input).collect();
+    max_pair_magnitude(&snailfish_numbers, |a, b| (a + b).magnitude())
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::day_18::{part_1, part_2};
+    use crate::day_18::generator::SnailfishGenerator;
+    use crate::day_18::reference::ReferenceNumber;
+    use crate::rng::XorShift64;
+    use crate::day_18::{part_1, part_1_arena, part_1_flat, part_2, part_2_arena, part_2_flat, part_2_parallel};
 
     #[test]
     fn example_magnitudes() {
@@ -282,11 +429,79 @@ mod tests {
         assert_eq!((a + b).magnitude(), 1_384);
     }
 
+    #[test]
+    fn std_traits_work() {
+        let a: SnailfishNumberTree = "[[[[4,3],4],4],[7,[[8,4],9]]]".parse().unwrap();
+        let b: SnailfishNumberTree = "[1,1]".parse().unwrap();
+        let expected: SnailfishNumberTree = "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]".parse().unwrap();
+        assert_eq!(&a + &b, expected);
+        assert_eq!(a.clone() + &b, expected);
+        let mut c = a.clone();
+        c += &b;
+        assert_eq!(c, expected);
+        assert_eq!([a.clone(), b.clone()].into_iter().sum::<SnailfishNumberTree>(), expected);
+
+        assert!(b < a && a < expected);
+        // Equal magnitudes (3 * 1 + 2 * 4 = 3 * 3 + 2 * 1), but different numbers
+        let (c, d): (SnailfishNumberTree, SnailfishNumberTree) = ("[1,4]".parse().unwrap(), "[3,1]".parse().unwrap());
+        assert_eq!(c.magnitude(), d.magnitude());
+        assert_ne!(c.cmp(&d), Ordering::Equal);
+
+        let unique: std::collections::HashSet<SnailfishNumberTree> = [a.clone(), b, a].into_iter().collect();
+        assert_eq!(unique.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "zero snailfish numbers")]
+    fn summing_nothing_panics() {
+        Vec::<SnailfishNumberTree>::new().into_iter().sum::<SnailfishNumberTree>();
+    }
+
+    #[test]
+    fn deep_and_unreduced_inputs_match_reference() {
+        let mut rng = XorShift64::from_seed(46);
+        let deep = SnailfishGenerator { max_depth: 8, max_value: 15 };
+        let shallow = SnailfishGenerator { max_depth: 5, max_value: 15 };
+        for _ in 0..500 {
+            let rules = ReductionRules { max_depth: rng.range(1, 4) as usize, split_threshold: rng.range(4, 12) as usize, ..ReductionRules::default() };
+            let input = deep.number(&mut rng).to_string();
+
+            let mut tree = SnailfishNumberTree::from_input(&input);
+            tree.reduce_with(&rules);
+            let mut reference = ReferenceNumber::from_input(&input);
+            reference.reduce(&rules);
+            assert_eq!(tree.to_string(), reference.to_string(), "{} {:?}", input, rules);
+            assert_eq!(tree.magnitude_with(&rules), reference.magnitude(&rules));
+
+            let rhs = shallow.number(&mut rng).to_string();
+            let sum = tree.add_with(SnailfishNumberTree::from_input(&rhs), &rules);
+            assert_eq!(sum.to_string(), reference.add(&ReferenceNumber::from_input(&rhs), &rules).to_string(), "{} + {}", input, rhs);
+        }
+    }
+
+    #[test]
+    fn explodes_leftmost_pair_of_deep_inputs() {
+        // The leftmost pair nested inside four pairs is [1,2], even though [3,[4,5]] goes deeper
+        let mut a = SnailfishNumberTree::from_input("[[[[[1,2],[3,[4,5]]],0],0],0]");
+        let trace = a.reduce_traced(&ReductionRules::default());
+        assert_eq!(trace.steps()[0].result(), "[[[[0,[5,[4,5]]],0],0],0]");
+        let mut reference = ReferenceNumber::from_input("[[[[[1,2],[3,[4,5]]],0],0],0]");
+        reference.reduce(&ReductionRules::default());
+        assert_eq!(a.to_string(), reference.to_string());
+    }
+
     #[test]
     fn simple_explodes() {
-        let mut a = SnailfishNumberTree::from_input("[[[[[9,8],1],2],3],4]");
-        a.reduce();
-        println!("{:?}", a);
+        for (input, expected) in [
+            ("[[[[[9,8],1],2],3],4]", "[[[[0,9],2],3],4]"),
+            ("[7,[6,[5,[4,[3,2]]]]]", "[7,[6,[5,[7,0]]]]"),
+            ("[[6,[5,[4,[3,2]]]],1]", "[[6,[5,[7,0]]],3]"),
+            ("[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]", "[[3,[2,[8,0]]],[9,[5,[7,0]]]]"),
+        ] {
+            let mut a = SnailfishNumberTree::from_input(input);
+            a.reduce();
+            assert_eq!(a.to_string(), expected);
+        }
     }
 
     #[test]
@@ -315,6 +530,79 @@ mod tests {
 [[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]"), 4_140);
     }
 
+    #[test]
+    fn scratch_sums_match_fresh_ones() {
+        let mut rng = XorShift64::from_seed(42);
+        let reduced = SnailfishGenerator { max_depth: 4, max_value: 9 };
+        let mut sum = SnailfishNumberTree::from_root(SnailfishNumberNode::leaf_from_regular_value(0));
+        for _ in 0..500 {
+            let (a, b) = (reduced.number(&mut rng), reduced.number(&mut rng));
+            sum.assign_sum(&a, &b);
+            assert_eq!(sum, &a + &b);
+            assert_eq!(sum.root.height, (&a + &b).root.height);
+        }
+    }
+
+    #[test]
+    fn variants_match_tree() {
+        let inputs = [
+            "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]
+[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]
+[[2,[[0,8],[3,4]]],[[[6,7],1],[7,[1,6]]]]
+[[[[2,4],7],[6,[0,5]]],[[[6,8],[2,8]],[[2,1],[4,5]]]]
+[7,[5,[[3,8],[1,4]]]]
+[[2,[2,2]],[8,[8,1]]]
+[2,9]
+[1,[[[9,3],9],[[9,0],[0,7]]]]
+[[[5,[7,4]],7],1]
+[[[[4,2],2],6],[8,7]]",
+            "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+[[[5,[2,8]],4],[5,[[9,9],0]]]
+[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
+[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
+[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
+[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
+[[[[5,4],[7,7]],8],[[8,3],8]]
+[[9,3],[[9,9],[6,[4,9]]]]
+[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
+[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]",
+        ];
+        for input in inputs {
+            let expected = part_1(input);
+            assert_eq!(part_1_flat(input), expected);
+            assert_eq!(part_1_arena(input), expected);
+
+            let expected = part_2(input);
+            assert_eq!(part_2_flat(input), expected);
+            assert_eq!(part_2_arena(input), expected);
+            assert_eq!(part_2_parallel(input), expected);
+        }
+        assert_eq!(part_2(inputs[1]), 3_993);
+    }
+
+    #[test]
+    fn exploding_into_the_largest_regular_number_saturates() {
+        // Reducing the split halves of usize::MAX all the way would take ages, a single explosion is what overflowed
+        let mut number = SnailfishNumberTree::from_input("[[[[[1,1],18446744073709551615],1],1],1]");
+        assert!(number.explode(ReductionRules::default().max_depth).is_some());
+        assert_eq!(number.to_string(), "[[[[0,18446744073709551615],1],1],1]");
+        assert_eq!(number.magnitude(), usize::MAX);
+    }
+
+    #[test]
+    fn part_2_skips_self_pairs() {
+        assert_eq!(part_2("[9,9]\n[1,1]"), 145);
+        assert_eq!(part_2_flat("[9,9]\n[1,1]"), 145);
+        assert_eq!(part_2_arena("[9,9]\n[1,1]"), 145);
+        assert_eq!(part_2_parallel("[9,9]\n[1,1]"), 145);
+
+        // A single number has no pair to add up
+        assert_eq!(part_2("[9,9]"), 0);
+        assert_eq!(part_2_flat("[9,9]"), 0);
+        assert_eq!(part_2_arena("[9,9]"), 0);
+        assert_eq!(part_2_parallel("[9,9]"), 0);
+    }
+
     #[test]
     fn pt_1_works() {
         assert_eq!(part_1("[[[2,[3,5]],[8,7]],[[9,3],2]]
@@ -0,0 +1,347 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Add;
+
+/// Pairs nested inside this many pairs explode.
+const EXPLODE_DEPTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Regular(usize),
+    Pair(usize, usize),
+}
+
+/// A node of the arena. Regular numbers are also linked to their neighbours to either side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Node {
+    kind: Kind,
+    parent: Option<usize>,
+    depth: usize,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+impl Node {
+    fn shifted(&self, offset: usize) -> Self {
+        let kind = match self.kind {
+            Kind::Regular(value) => Kind::Regular(value),
+            Kind::Pair(left, right) => Kind::Pair(left + offset, right + offset),
+        };
+        let shift = |index: Option<usize>| index.map(|index| index + offset);
+        Node { kind, parent: shift(self.parent), depth: self.depth, prev: shift(self.prev), next: shift(self.next) }
+    }
+}
+
+/// Snailfish number with its nodes in a single vector, referring to each other by index. Parent links and the list
+/// of regular numbers from left to right make the neighbours of an exploding pair a lookup away, and the nodes of
+/// exploded pairs get reused by splits.
+#[derive(Debug, Clone)]
+pub struct ArenaSnailfishNumber {
+    nodes: Vec<Node>,
+    root: usize,
+    first_regular: usize,
+    free: Vec<usize>,
+}
+
+#[allow(dead_code)]
+impl ArenaSnailfishNumber {
+    pub fn from_input(input: &str) -> Self {
+        let mut number = ArenaSnailfishNumber { nodes: Vec::with_capacity(input.len() / 2), root: 0, first_regular: 0, free: Vec::new() };
+        // Pairs not closed yet, and whether their left half is known
+        let mut open_pairs: Vec<(usize, bool)> = Vec::new();
+        let mut last_regular = None;
+        let mut chars = input.trim().chars().peekable();
+        while let Some(c) = chars.next() {
+            let kind = match c {
+                '[' => Kind::Pair(0, 0),
+                ']' => {
+                    open_pairs.pop();
+                    continue
+                },
+                ',' => continue,
+                digit if digit.is_ascii_digit() => {
+                    let mut value = digit.to_digit(10).unwrap() as usize;
+                    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                        value = value * 10 + digit as usize;
+                        chars.next();
+                    }
+                    Kind::Regular(value)
+                },
+                _ => panic!("Unexpected char in ArenaSnailfishNumber"),
+            };
+
+            let index = number.nodes.len();
+            let parent = open_pairs.last_mut().map(|(parent, has_left)| {
+                let Kind::Pair(left, right) = &mut number.nodes[*parent].kind else { unreachable!() };
+                *if *has_left { right } else { left } = index;
+                *has_left = true;
+                *parent
+            });
+            number.nodes.push(Node { kind, parent, depth: open_pairs.len(), prev: None, next: None });
+            match kind {
+                Kind::Pair(..) => open_pairs.push((index, false)),
+                Kind::Regular(_) => {
+                    match last_regular {
+                        Some(last) => number.link(last, index),
+                        None => number.first_regular = index,
+                    }
+                    last_regular = Some(index);
+                },
+            }
+        }
+        number
+    }
+
+    fn allocate(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            },
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            },
+        }
+    }
+
+    fn link(&mut self, prev: usize, next: usize) {
+        self.nodes[prev].next = Some(next);
+        self.nodes[next].prev = Some(prev);
+    }
+
+    /// Links `index` in between the given regular numbers, or makes it the first one.
+    fn link_between(&mut self, prev: Option<usize>, index: usize, next: Option<usize>) {
+        match prev {
+            Some(prev) => self.link(prev, index),
+            None => {
+                self.nodes[index].prev = None;
+                self.first_regular = index;
+            },
+        }
+        match next {
+            Some(next) => self.link(index, next),
+            None => self.nodes[index].next = None,
+        }
+    }
+
+    fn regular_value(&self, index: usize) -> Option<usize> {
+        match self.nodes[index].kind {
+            Kind::Regular(value) => Some(value),
+            Kind::Pair(..) => None,
+        }
+    }
+
+    fn add_to_regular(&mut self, index: Option<usize>, to_add: usize) {
+        if let Some(Kind::Regular(value)) = index.map(|index| &mut self.nodes[index].kind) {
+            *value = value.saturating_add(to_add);
+        }
+    }
+
+    /// Whether the parent of the regular number at `index` is a pair of two regular numbers that explodes.
+    fn exploding_parent(&self, index: usize) -> Option<usize> {
+        let parent = self.nodes[index].parent?;
+        let Kind::Pair(left, right) = self.nodes[parent].kind else { unreachable!() };
+        let is_regular_pair = self.regular_value(left).is_some() && self.regular_value(right).is_some();
+        (is_regular_pair && self.nodes[parent].depth >= EXPLODE_DEPTH).then_some(parent)
+    }
+
+    /// Explodes the pair of two regular numbers at `index`, which becomes the regular number 0.
+    fn explode(&mut self, index: usize) {
+        let Kind::Pair(left, right) = self.nodes[index].kind else { unreachable!() };
+        let (prev, next) = (self.nodes[left].prev, self.nodes[right].next);
+        self.add_to_regular(prev, self.regular_value(left).unwrap());
+        self.add_to_regular(next, self.regular_value(right).unwrap());
+        self.nodes[index].kind = Kind::Regular(0);
+        self.link_between(prev, index, next);
+        self.free.extend([left, right]);
+    }
+
+    /// Splits the regular number at `index` into a pair, returning the index of its left half.
+    fn split(&mut self, index: usize) -> usize {
+        let Node { kind: Kind::Regular(value), depth, prev, next, .. } = self.nodes[index] else { unreachable!() };
+        let half = |value| Node { kind: Kind::Regular(value), parent: Some(index), depth: depth + 1, prev: None, next: None };
+        let left = self.allocate(half(value / 2));
+        let right = self.allocate(half(value.div_ceil(2)));
+        self.nodes[index].kind = Kind::Pair(left, right);
+        self.nodes[index].prev = None;
+        self.nodes[index].next = None;
+        self.link_between(prev, left, Some(right));
+        self.link_between(Some(left), right, next);
+        left
+    }
+
+    /// Explodes the leftmost exploding pair until there is none, then splits the leftmost regular number of 10 or
+    /// more and repeats. Everything left of the regular number looked at has been dealt with, so after a change only
+    /// the regular number before it needs another look.
+    pub fn reduce(&mut self) {
+        let mut current = Some(self.first_regular);
+        while let Some(index) = current {
+            current = match self.exploding_parent(index) {
+                Some(pair) => {
+                    self.explode(pair);
+                    Some(self.nodes[pair].prev.unwrap_or(pair))
+                },
+                None => self.nodes[index].next,
+            }
+        }
+
+        // No pair explodes now, so only the pair a split makes can
+        let mut current = Some(self.first_regular);
+        while let Some(index) = current {
+            if self.regular_value(index).unwrap() < 10 {
+                current = self.nodes[index].next;
+                continue
+            }
+            let left = self.split(index);
+            current = if self.nodes[index].depth >= EXPLODE_DEPTH {
+                self.explode(index);
+                Some(self.nodes[index].prev.unwrap_or(index))
+            } else {
+                Some(left)
+            };
+        }
+    }
+
+    pub fn magnitude(&self) -> usize {
+        let mut magnitudes = Vec::new();
+        let mut stack = vec![(self.root, false)];
+        while let Some((index, children_done)) = stack.pop() {
+            match self.nodes[index].kind {
+                Kind::Regular(value) => magnitudes.push(value),
+                Kind::Pair(left, right) if !children_done => stack.extend([(index, true), (right, false), (left, false)]),
+                Kind::Pair(..) => {
+                    let right = magnitudes.pop().unwrap();
+                    let left = magnitudes.pop().unwrap();
+                    magnitudes.push(left.saturating_mul(3).saturating_add(right.saturating_mul(2)));
+                },
+            }
+        }
+        magnitudes[0]
+    }
+
+    fn last_regular(&self) -> usize {
+        let mut index = self.root;
+        while let Kind::Pair(_, right) = self.nodes[index].kind {
+            index = right;
+        }
+        index
+    }
+}
+
+enum Output {
+    Node(usize),
+    Text(&'static str),
+}
+
+impl Display for ArenaSnailfishNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut stack = vec![Output::Node(self.root)];
+        while let Some(output) = stack.pop() {
+            match output {
+                Output::Text(text) => write!(f, "{}", text)?,
+                Output::Node(index) => match self.nodes[index].kind {
+                    Kind::Regular(value) => write!(f, "{}", value)?,
+                    Kind::Pair(left, right) => {
+                        write!(f, "[")?;
+                        stack.extend([Output::Text("]"), Output::Node(right), Output::Text(","), Output::Node(left)]);
+                    },
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Add<&ArenaSnailfishNumber> for &ArenaSnailfishNumber {
+    type Output = ArenaSnailfishNumber;
+
+    fn add(self, rhs: &ArenaSnailfishNumber) -> Self::Output {
+        let offset = self.nodes.len();
+        let mut nodes = Vec::with_capacity(offset + rhs.nodes.len() + 1);
+        nodes.extend(self.nodes.iter().chain(&rhs.nodes).enumerate().map(|(i, node)| {
+            let node = if i < offset { *node } else { node.shifted(offset) };
+            Node { depth: node.depth + 1, ..node }
+        }));
+        let (left, right) = (self.root, rhs.root + offset);
+        let root = nodes.len();
+        nodes.push(Node { kind: Kind::Pair(left, right), parent: None, depth: 0, prev: None, next: None });
+        nodes[left].parent = Some(root);
+        nodes[right].parent = Some(root);
+
+        let free = self.free.iter().copied().chain(rhs.free.iter().map(|index| index + offset)).collect();
+        let mut res = ArenaSnailfishNumber { nodes, root, first_regular: self.first_regular, free };
+        res.link(self.last_regular(), rhs.first_regular + offset);
+        res.reduce();
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_18::generator::SnailfishGenerator;
+    use crate::day_18::SnailfishNumberTree;
+    use crate::rng::XorShift64;
+
+    fn reduced(input: &str) -> String {
+        let mut number = ArenaSnailfishNumber::from_input(input);
+        number.reduce();
+        number.to_string()
+    }
+
+    #[test]
+    fn exploding_into_the_largest_regular_number_saturates() {
+        let mut number = ArenaSnailfishNumber::from_input("[[[[[1,1],18446744073709551615],1],1],1]");
+        let pair = number.exploding_parent(number.first_regular).unwrap();
+        number.explode(pair);
+        assert_eq!(number.to_string(), "[[[[0,18446744073709551615],1],1],1]");
+        assert_eq!(number.magnitude(), usize::MAX);
+    }
+
+    #[test]
+    fn round_trips_through_strings() {
+        for input in ["[1,2]", "[[1,2],[[3,4],5]]", "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]", "[[[[1,1],[2,2]],[3,3]],[4,4]]"] {
+            assert_eq!(ArenaSnailfishNumber::from_input(input).to_string(), input);
+        }
+    }
+
+    #[test]
+    fn aoc_explodes_work() {
+        assert_eq!(reduced("[[[[[9,8],1],2],3],4]"), "[[[[0,9],2],3],4]");
+        assert_eq!(reduced("[7,[6,[5,[4,[3,2]]]]]"), "[7,[6,[5,[7,0]]]]");
+        assert_eq!(reduced("[[6,[5,[4,[3,2]]]],1]"), "[[6,[5,[7,0]]],3]");
+        assert_eq!(reduced("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]"), "[[3,[2,[8,0]]],[9,[5,[7,0]]]]");
+    }
+
+    #[test]
+    fn aoc_addition_works() {
+        let a = ArenaSnailfishNumber::from_input("[[[[4,3],4],4],[7,[[8,4],9]]]");
+        let b = ArenaSnailfishNumber::from_input("[1,1]");
+        assert_eq!((&a + &b).to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+        assert_eq!((&a + &b).magnitude(), 1_384);
+        assert_eq!(ArenaSnailfishNumber::from_input("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]").magnitude(), 3_488);
+    }
+
+    #[test]
+    fn matches_tree_on_random_numbers() {
+        let mut rng = XorShift64::from_seed(51);
+        let unreduced = SnailfishGenerator { max_depth: 7, max_value: 25 };
+        let reduced = SnailfishGenerator { max_depth: 4, max_value: 9 };
+        for _ in 0..500 {
+            let mut tree = unreduced.number(&mut rng);
+            let mut arena = ArenaSnailfishNumber::from_input(&tree.to_string());
+            tree.reduce();
+            arena.reduce();
+            assert_eq!(arena.to_string(), tree.to_string());
+
+            // Sums reuse the nodes freed by explosions, so keep adding to the same number
+            let summand = reduced.number(&mut rng);
+            let tree = tree + &summand;
+            let arena = &arena + &ArenaSnailfishNumber::from_input(&summand.to_string());
+            assert_eq!(arena.to_string(), tree.to_string());
+            assert_eq!(arena.magnitude(), tree.magnitude());
+            let tree = tree + SnailfishNumberTree::from_input(&arena.to_string());
+            assert_eq!((&arena + &arena).to_string(), tree.to_string());
+        }
+    }
+}
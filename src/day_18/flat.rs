@@ -0,0 +1,167 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Add;
+
+/// Pairs nested this deep explode.
+const EXPLODE_DEPTH: usize = 5;
+
+/// A regular number together with the number of pairs it is nested in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Element {
+    value: usize,
+    depth: usize,
+}
+
+/// Snailfish number stored as its regular numbers from left to right, each with its depth. The neighbours an
+/// exploding pair adds its values to are then simply the adjacent elements, and the whole number is a single
+/// allocation rather than one per node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatSnailfishNumber {
+    elements: Vec<Element>,
+}
+
+#[allow(dead_code)]
+impl FlatSnailfishNumber {
+    pub fn from_input(input: &str) -> Self {
+        let mut elements = Vec::with_capacity(input.len() / 2);
+        let mut depth = 0;
+        let mut value = None;
+        for c in input.trim().chars() {
+            match c {
+                '[' => depth += 1,
+                ']' | ',' => {
+                    if let Some(value) = value.take() {
+                        elements.push(Element { value, depth });
+                    }
+                    if c == ']' {
+                        depth -= 1;
+                    }
+                },
+                digit if digit.is_ascii_digit() => value = Some(value.unwrap_or(0) * 10 + digit.to_digit(10).unwrap() as usize),
+                _ => panic!("Unexpected char in FlatSnailfishNumber"),
+            }
+        }
+        if let Some(value) = value {
+            elements.push(Element { value, depth });
+        }
+        FlatSnailfishNumber { elements }
+    }
+
+    /// Explodes the leftmost pair nested inside four pairs, if there is one.
+    fn explode(&mut self) -> bool {
+        let Some(i) = self.elements.iter().position(|element| element.depth >= EXPLODE_DEPTH) else { return false };
+        let (left, right) = (self.elements[i], self.elements[i + 1]);
+        if i > 0 {
+            self.elements[i - 1].value = self.elements[i - 1].value.saturating_add(left.value);
+        }
+        if let Some(next) = self.elements.get_mut(i + 2) {
+            next.value = next.value.saturating_add(right.value);
+        }
+        self.elements[i] = Element { value: 0, depth: left.depth - 1 };
+        self.elements.remove(i + 1);
+        true
+    }
+
+    /// Splits the leftmost regular number of 10 or more, if there is one.
+    fn split(&mut self) -> bool {
+        let Some(i) = self.elements.iter().position(|element| element.value >= 10) else { return false };
+        let Element { value, depth } = self.elements[i];
+        self.elements[i] = Element { value: value / 2, depth: depth + 1 };
+        self.elements.insert(i + 1, Element { value: value.div_ceil(2), depth: depth + 1 });
+        true
+    }
+
+    pub fn reduce(&mut self) {
+        while self.explode() || self.split() {}
+    }
+
+    /// Folds the adjacent elements of equal depth, which are exactly the pairs of regular numbers, bottom up.
+    pub fn magnitude(&self) -> usize {
+        let mut stack: Vec<Element> = Vec::with_capacity(EXPLODE_DEPTH + 1);
+        for &element in &self.elements {
+            let mut element = element;
+            while let Some(&top) = stack.last() {
+                if top.depth != element.depth {
+                    break
+                }
+                stack.pop();
+                element = Element { value: top.value.saturating_mul(3).saturating_add(element.value.saturating_mul(2)), depth: element.depth - 1 };
+            }
+            stack.push(element);
+        }
+        stack[0].value
+    }
+
+    fn fmt_pair(&self, f: &mut Formatter<'_>, index: &mut usize, depth: usize) -> std::fmt::Result {
+        let element = self.elements[*index];
+        if element.depth == depth {
+            *index += 1;
+            return write!(f, "{}", element.value)
+        }
+        write!(f, "[")?;
+        self.fmt_pair(f, index, depth + 1)?;
+        write!(f, ",")?;
+        self.fmt_pair(f, index, depth + 1)?;
+        write!(f, "]")
+    }
+}
+
+impl Display for FlatSnailfishNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_pair(f, &mut 0, 0)
+    }
+}
+
+impl Add<&FlatSnailfishNumber> for &FlatSnailfishNumber {
+    type Output = FlatSnailfishNumber;
+
+    fn add(self, rhs: &FlatSnailfishNumber) -> Self::Output {
+        let mut elements = Vec::with_capacity(self.elements.len() + rhs.elements.len() + 4);
+        elements.extend(self.elements.iter().chain(&rhs.elements).map(|element| Element { value: element.value, depth: element.depth + 1 }));
+        let mut res = FlatSnailfishNumber { elements };
+        res.reduce();
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reduced(input: &str) -> String {
+        let mut number = FlatSnailfishNumber::from_input(input);
+        number.reduce();
+        number.to_string()
+    }
+
+    #[test]
+    fn exploding_into_the_largest_regular_number_saturates() {
+        let mut number = FlatSnailfishNumber::from_input("[[[[[1,1],18446744073709551615],1],1],1]");
+        assert!(number.explode());
+        assert_eq!(number.to_string(), "[[[[0,18446744073709551615],1],1],1]");
+        assert_eq!(number.magnitude(), usize::MAX);
+    }
+
+    #[test]
+    fn round_trips_through_strings() {
+        for input in ["[1,2]", "[[1,2],[[3,4],5]]", "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]", "[[[[1,1],[2,2]],[3,3]],[4,4]]"] {
+            assert_eq!(FlatSnailfishNumber::from_input(input).to_string(), input);
+        }
+    }
+
+    #[test]
+    fn aoc_explodes_work() {
+        assert_eq!(reduced("[[[[[9,8],1],2],3],4]"), "[[[[0,9],2],3],4]");
+        assert_eq!(reduced("[7,[6,[5,[4,[3,2]]]]]"), "[7,[6,[5,[7,0]]]]");
+        assert_eq!(reduced("[[6,[5,[4,[3,2]]]],1]"), "[[6,[5,[7,0]]],3]");
+        assert_eq!(reduced("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]"), "[[3,[2,[8,0]]],[9,[5,[7,0]]]]");
+    }
+
+    #[test]
+    fn aoc_addition_works() {
+        let a = FlatSnailfishNumber::from_input("[[[[4,3],4],4],[7,[[8,4],9]]]");
+        let b = FlatSnailfishNumber::from_input("[1,1]");
+        assert_eq!((&a + &b).to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+        assert_eq!((&a + &b).magnitude(), 1_384);
+        assert_eq!(FlatSnailfishNumber::from_input("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]").magnitude(), 3_488);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::rng::XorShift64;
+
+use super::{SnailfishNumberNode, SnailfishNumberTree};
+
+/// Generates random snailfish numbers: pairs nested at most `max_depth` deep, regular numbers up to `max_value`.
+/// Both bounds may exceed the reduction rules to produce numbers that still need reducing.
+pub struct SnailfishGenerator {
+    pub max_depth: usize,
+    pub max_value: usize,
+}
+
+impl SnailfishGenerator {
+    pub fn number(&self, rng: &mut XorShift64) -> SnailfishNumberTree {
+        assert!(self.max_depth > 0, "A snailfish number is at least one pair");
+        SnailfishNumberTree::from_root(self.pair_with_depth(rng, self.max_depth))
+    }
+
+    fn pair_with_depth(&self, rng: &mut XorShift64, depth: usize) -> SnailfishNumberNode {
+        let left = self.element_with_depth(rng, depth - 1);
+        let right = self.element_with_depth(rng, depth - 1);
+        SnailfishNumberNode::node_with_children(Box::new(left), Box::new(right))
+    }
+
+    fn element_with_depth(&self, rng: &mut XorShift64, depth: usize) -> SnailfishNumberNode {
+        if depth == 0 || rng.chance(1, 3) {
+            return SnailfishNumberNode::leaf_from_regular_value(rng.below(self.max_value + 1))
+        }
+        self.pair_with_depth(rng, depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_18::reference::ReferenceNumber;
+    use crate::day_18::rules::{ReductionRules, SplitRounding};
+
+    fn random_rules(rng: &mut XorShift64) -> ReductionRules {
+        ReductionRules {
+            max_depth: rng.range(1, 5) as usize,
+            split_threshold: rng.range(2, 12) as usize,
+            split_rounding: if rng.chance(1, 2) { SplitRounding::LeftDown } else { SplitRounding::LeftUp },
+            magnitude_weights: (rng.range(1, 4) as usize, rng.range(1, 4) as usize),
+        }
+    }
+
+    #[test]
+    fn numbers_round_trip() {
+        let mut rng = XorShift64::from_seed(47);
+        let generator = SnailfishGenerator { max_depth: 8, max_value: 1_000 };
+        for _ in 0..1_000 {
+            let number = generator.number(&mut rng);
+            let text = number.to_string();
+            assert_eq!(SnailfishNumberTree::from_input(&text), number);
+            assert_eq!(ReferenceNumber::from_input(&text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn reduction_matches_reference() {
+        let mut rng = XorShift64::from_seed(48);
+        let generator = SnailfishGenerator { max_depth: 7, max_value: 20 };
+        for _ in 0..1_000 {
+            let rules = random_rules(&mut rng);
+            let mut number = generator.number(&mut rng);
+            let mut reference = ReferenceNumber::from_input(&number.to_string());
+            number.reduce_with(&rules);
+            reference.reduce(&rules);
+            assert_eq!(number.to_string(), reference.to_string(), "{:?}", rules);
+            assert_eq!(number.magnitude_with(&rules), reference.magnitude(&rules), "{:?}", rules);
+        }
+    }
+
+    #[test]
+    fn addition_matches_reference() {
+        let mut rng = XorShift64::from_seed(49);
+        for _ in 0..200 {
+            let rules = random_rules(&mut rng);
+            let generator = SnailfishGenerator { max_depth: rules.max_depth, max_value: rules.split_threshold - 1 };
+            let numbers: Vec<SnailfishNumberTree> = (0..=rng.below(6)).map(|_| generator.number(&mut rng)).collect();
+
+            let mut reference = ReferenceNumber::from_input(&numbers[0].to_string());
+            for number in &numbers[1..] {
+                reference = reference.add(&ReferenceNumber::from_input(&number.to_string()), &rules);
+            }
+            let sum = numbers.into_iter().reduce(|sum, number| sum.add_with(number, &rules)).unwrap();
+            assert_eq!(sum.to_string(), reference.to_string(), "{:?}", rules);
+            assert_eq!(sum.magnitude_with(&rules), reference.magnitude(&rules), "{:?}", rules);
+        }
+    }
+
+    #[test]
+    fn reduced_magnitudes_are_bounded() {
+        let mut rng = XorShift64::from_seed(50);
+        let generator = SnailfishGenerator { max_depth: 7, max_value: 30 };
+        for _ in 0..1_000 {
+            let rules = random_rules(&mut rng);
+            let mut number = generator.number(&mut rng);
+            number.reduce_with(&rules);
+            assert!(number.magnitude_with(&rules) <= rules.max_magnitude(), "{} {:?}", number, rules);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use super::parse::ParseSnailfishError;
+use super::SnailfishNumberTree;
+
+#[derive(Debug)]
+pub enum HomeworkError {
+    Unreadable { file: String, error: std::io::Error },
+    InvalidNumber { file: String, line: usize, error: ParseSnailfishError },
+}
+
+impl Display for HomeworkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HomeworkError::Unreadable { file, error } => write!(f, "{}: cannot read the homework: {}", file, error),
+            HomeworkError::InvalidNumber { file, line, error } => write!(f, "{}:{}: {}", file, line, error),
+        }
+    }
+}
+
+impl std::error::Error for HomeworkError {}
+
+/// A block of consecutive non-blank lines of a homework file, each a snailfish number to add up in order.
+pub struct Problem {
+    file: String,
+    numbers: Vec<(usize, SnailfishNumberTree)>,
+}
+
+/// The sum of the numbers of a problem up to and including the one on `line`.
+pub struct RunningSum {
+    line: usize,
+    sum: SnailfishNumberTree,
+    magnitude: usize,
+}
+
+#[allow(dead_code)]
+impl RunningSum {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn sum(&self) -> &SnailfishNumberTree {
+        &self.sum
+    }
+
+    pub fn magnitude(&self) -> usize {
+        self.magnitude
+    }
+}
+
+#[allow(dead_code)]
+impl Problem {
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    pub fn first_line(&self) -> usize {
+        self.numbers[0].0
+    }
+
+    pub fn last_line(&self) -> usize {
+        self.numbers[self.numbers.len() - 1].0
+    }
+
+    pub fn running_sums(&self) -> Vec<RunningSum> {
+        let mut running_sums: Vec<RunningSum> = Vec::with_capacity(self.numbers.len());
+        for (line, number) in &self.numbers {
+            let sum = match running_sums.last() {
+                Some(previous) => &previous.sum + number,
+                None => number.clone(),
+            };
+            running_sums.push(RunningSum { line: *line, magnitude: sum.magnitude(), sum });
+        }
+        running_sums
+    }
+}
+
+/// Splits the text of the homework `file` into its problems, which are separated by blank lines. Line numbers start at 1.
+pub fn parse_homework(file: &str, text: &str) -> Result<Vec<Problem>, HomeworkError> {
+    let mut problems = Vec::new();
+    let mut numbers = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            if !numbers.is_empty() {
+                problems.push(Problem { file: file.to_string(), numbers: std::mem::take(&mut numbers) });
+            }
+            continue
+        }
+        let number = line.parse().map_err(|error| HomeworkError::InvalidNumber { file: file.to_string(), line: i + 1, error })?;
+        numbers.push((i + 1, number));
+    }
+    if !numbers.is_empty() {
+        problems.push(Problem { file: file.to_string(), numbers });
+    }
+    Ok(problems)
+}
+
+/// The problems of all the homework files, in order.
+pub fn read_homework(paths: &[impl AsRef<Path>]) -> Result<Vec<Problem>, HomeworkError> {
+    let mut problems = Vec::new();
+    for path in paths {
+        let file = path.as_ref().display().to_string();
+        let text = std::fs::read_to_string(path).map_err(|error| HomeworkError::Unreadable { file: file.clone(), error })?;
+        problems.extend(parse_homework(&file, &text)?);
+    }
+    Ok(problems)
+}
+
+/// Lists the running sums of each problem, with their magnitudes.
+pub struct Report<'a>(pub &'a [Problem]);
+
+impl Display for Report<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, problem) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}, lines {}-{}:", problem.file, problem.first_line(), problem.last_line())?;
+            for running_sum in problem.running_sums() {
+                writeln!(f, "{:>6}  {:>6}  {}", running_sum.line, running_sum.magnitude, running_sum.sum)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOMEWORK: &str = "\
+[1,1]
+[2,2]
+[3,3]
+[4,4]
+
+[[[[4,3],4],4],[7,[[8,4],9]]]
+[1,1]
+
+
+[9,1]
+";
+
+    #[test]
+    fn problems_are_separated_by_blank_lines() {
+        let problems = parse_homework("homework.txt", HOMEWORK).unwrap();
+        assert_eq!(problems.iter().map(|problem| (problem.first_line(), problem.last_line())).collect::<Vec<_>>(), [(1, 4), (6, 7), (10, 10)]);
+
+        let running_sums = problems[0].running_sums();
+        assert_eq!(running_sums.iter().map(|running_sum| running_sum.line()).collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(running_sums[1].sum().to_string(), "[[1,1],[2,2]]");
+        assert_eq!(running_sums[3].sum().to_string(), "[[[[1,1],[2,2]],[3,3]],[4,4]]");
+        assert_eq!(running_sums[3].magnitude(), 445);
+
+        let running_sums = problems[1].running_sums();
+        assert_eq!(running_sums[1].sum().to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+        assert_eq!(running_sums[1].magnitude(), 1_384);
+        assert_eq!(problems[2].running_sums()[0].magnitude(), 29);
+    }
+
+    #[test]
+    fn report_lists_running_sums() {
+        let problems = parse_homework("homework.txt", "[9,1]\n[1,9]\n\n[1,1]").unwrap();
+        assert_eq!(Report(&problems).to_string(), "\
+homework.txt, lines 1-2:
+     1      29  [9,1]
+     2     129  [[9,1],[1,9]]
+
+homework.txt, lines 4-4:
+     4       5  [1,1]
+");
+    }
+
+    #[test]
+    fn errors_name_file_and_line() {
+        let error = parse_homework("homework.txt", "[1,1]\n\n[2,2]\n[3;3]").err().unwrap();
+        assert!(matches!(error, HomeworkError::InvalidNumber { line: 4, .. }));
+        assert_eq!(error.to_string(), "homework.txt:4: expected ',' but got ';' at position 2");
+
+        let error = read_homework(&["does/not/exist.txt"]).err().unwrap();
+        assert!(error.to_string().starts_with("does/not/exist.txt: cannot read the homework: "));
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use super::{SnailfishNumberNode, SnailfishNumberTree};
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSnailfishError {
+    UnexpectedChar { position: usize, found: char, expected: &'static str },
+    UnexpectedEnd { expected: &'static str },
+    RegularNumberTooLarge { position: usize },
+    TrailingInput { position: usize },
+}
+
+impl Display for ParseSnailfishError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseSnailfishError::UnexpectedChar { position, found, expected } => write!(f, "expected {} but got {:?} at position {}", expected, found, position),
+            ParseSnailfishError::UnexpectedEnd { expected } => write!(f, "expected {} but the number ended", expected),
+            ParseSnailfishError::RegularNumberTooLarge { position } => write!(f, "regular number at position {} is too large", position),
+            ParseSnailfishError::TrailingInput { position } => write!(f, "unexpected input after the number at position {}", position),
+        }
+    }
+}
+
+impl std::error::Error for ParseSnailfishError {}
+
+const ELEMENT: &str = "'[' or a regular number";
+
+fn unexpected(input: &str, position: usize, expected: &'static str) -> ParseSnailfishError {
+    match input[position..].chars().next() {
+        Some(found) => ParseSnailfishError::UnexpectedChar { position, found, expected },
+        None => ParseSnailfishError::UnexpectedEnd { expected },
+    }
+}
+
+fn expect(input: &str, position: &mut usize, expected_byte: u8, expected: &'static str) -> Result<(), ParseSnailfishError> {
+    if input.as_bytes().get(*position) != Some(&expected_byte) {
+        return Err(unexpected(input, *position, expected))
+    }
+    *position += 1;
+    Ok(())
+}
+
+fn parse_regular_number(input: &str, position: &mut usize) -> Result<usize, ParseSnailfishError> {
+    let start = *position;
+    let mut number: usize = 0;
+    while let Some(digit) = input.as_bytes().get(*position).filter(|byte| byte.is_ascii_digit()) {
+        number = number.checked_mul(10).and_then(|number| number.checked_add((digit - b'0') as usize))
+            .ok_or(ParseSnailfishError::RegularNumberTooLarge { position: start })?;
+        *position += 1;
+    }
+    Ok(number)
+}
+
+/// Parses the number starting at byte `position` up to the end of `input`. Pairs still waiting for their closing
+/// bracket live on an explicit stack rather than the call stack.
+fn parse_number(input: &str, mut position: usize) -> Result<Box<SnailfishNumberNode>, ParseSnailfishError> {
+    // Left halves of the open pairs, `None` until their comma is reached
+    let mut open_pairs: Vec<Option<Box<SnailfishNumberNode>>> = Vec::new();
+    loop {
+        let mut element = match input.as_bytes().get(position) {
+            Some(b'[') => {
+                open_pairs.push(None);
+                position += 1;
+                continue
+            },
+            Some(byte) if byte.is_ascii_digit() => {
+                Box::new(SnailfishNumberNode::leaf_from_regular_value(parse_regular_number(input, &mut position)?))
+            },
+            _ => return Err(unexpected(input, position, ELEMENT)),
+        };
+
+        // Close all pairs the element completes, up to the first one it is the left half of
+        loop {
+            match open_pairs.last_mut() {
+                None if position < input.len() => return Err(ParseSnailfishError::TrailingInput { position }),
+                None => return Ok(element),
+                Some(left) if left.is_none() => {
+                    expect(input, &mut position, b',', "','")?;
+                    *left = Some(element);
+                    break
+                },
+                Some(_) => {
+                    expect(input, &mut position, b']', "']'")?;
+                    let left = open_pairs.pop().unwrap().unwrap();
+                    element = Box::new(SnailfishNumberNode::node_with_children(left, element));
+                },
+            }
+        }
+    }
+}
+
+/// Parses a snailfish number, ignoring surrounding whitespace. Positions in errors count bytes from the start of `input`.
+impl FromStr for SnailfishNumberTree {
+    type Err = ParseSnailfishError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let leading_whitespace = input.len() - input.trim_start().len();
+        let root = parse_number(input.trim_end(), leading_whitespace)?;
+        Ok(SnailfishNumberTree { root })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_is_ignored() {
+        let number: SnailfishNumberTree = "  [[1,2],[[3,4],5]]\r\n".parse().unwrap();
+        assert_eq!(number.to_string(), "[[1,2],[[3,4],5]]");
+    }
+
+    #[test]
+    fn errors_are_descriptive() {
+        let error = |input: &str| input.parse::<SnailfishNumberTree>().err().unwrap();
+        assert_eq!(error("[1,2"), ParseSnailfishError::UnexpectedEnd { expected: "']'" });
+        assert_eq!(error("[1;2]"), ParseSnailfishError::UnexpectedChar { position: 2, found: ';', expected: "','" });
+        assert_eq!(error(" [x,2]"), ParseSnailfishError::UnexpectedChar { position: 2, found: 'x', expected: "'[' or a regular number" });
+        assert_eq!(error("[1,2]]"), ParseSnailfishError::TrailingInput { position: 5 });
+        assert_eq!(error("[1,99999999999999999999999]"), ParseSnailfishError::RegularNumberTooLarge { position: 3 });
+        assert_eq!(error("").to_string(), "expected '[' or a regular number but the number ended");
+        assert_eq!(error("[[1,2],é]"), ParseSnailfishError::UnexpectedChar { position: 7, found: 'é', expected: "'[' or a regular number" });
+        assert_eq!(error("[[1,2],[3,4]"), ParseSnailfishError::UnexpectedEnd { expected: "']'" });
+    }
+}
@@ -0,0 +1,122 @@
+//! Naive snailfish arithmetic on the token string, written straight from the puzzle statement to test against.
+
+use std::fmt::{Display, Formatter};
+
+use super::rules::ReductionRules;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Number(usize),
+}
+
+pub struct ReferenceNumber {
+    tokens: Vec<Token>,
+}
+
+impl ReferenceNumber {
+    pub fn from_input(input: &str) -> Self {
+        let mut tokens = Vec::new();
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '[' => tokens.push(Token::Open),
+                ']' => tokens.push(Token::Close),
+                ',' => {},
+                _ => {
+                    let mut number = c.to_digit(10).unwrap() as usize;
+                    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                        number = number * 10 + digit as usize;
+                        chars.next();
+                    }
+                    tokens.push(Token::Number(number));
+                },
+            }
+        }
+        ReferenceNumber { tokens }
+    }
+
+    pub fn add(&self, rhs: &ReferenceNumber, rules: &ReductionRules) -> Self {
+        let mut tokens = vec![Token::Open];
+        tokens.extend(&self.tokens);
+        tokens.extend(&rhs.tokens);
+        tokens.push(Token::Close);
+        let mut res = ReferenceNumber { tokens };
+        res.reduce(rules);
+        res
+    }
+
+    fn explode(&mut self, max_depth: usize) -> bool {
+        let mut depth = 0;
+        for i in 0..self.tokens.len() {
+            match self.tokens[i] {
+                Token::Open => depth += 1,
+                Token::Close => depth -= 1,
+                Token::Number(_) => continue,
+            }
+            // The pair opened here is nested inside depth - 1 pairs
+            let Some(&[Token::Open, Token::Number(left), Token::Number(right), Token::Close]) = self.tokens.get(i..i + 4) else { continue };
+            if depth <= max_depth {
+                continue
+            }
+            if let Some(Token::Number(number)) = self.tokens[..i].iter_mut().rev().find(|token| matches!(token, Token::Number(_))) {
+                *number = number.saturating_add(left);
+            }
+            if let Some(Token::Number(number)) = self.tokens[i + 4..].iter_mut().find(|token| matches!(token, Token::Number(_))) {
+                *number = number.saturating_add(right);
+            }
+            self.tokens.splice(i..i + 4, [Token::Number(0)]);
+            return true
+        }
+        false
+    }
+
+    fn split(&mut self, rules: &ReductionRules) -> bool {
+        let Some(i) = self.tokens.iter().position(|token| matches!(token, Token::Number(number) if *number >= rules.split_threshold)) else { return false };
+        let Token::Number(number) = self.tokens[i] else { unreachable!() };
+        let (left, right) = rules.split_halves(number);
+        self.tokens.splice(i..=i, [Token::Open, Token::Number(left), Token::Number(right), Token::Close]);
+        true
+    }
+
+    pub fn reduce(&mut self, rules: &ReductionRules) {
+        while self.explode(rules.max_depth) || self.split(rules) {}
+    }
+
+    fn magnitude_from(&self, i: &mut usize, rules: &ReductionRules) -> usize {
+        let token = self.tokens[*i];
+        *i += 1;
+        match token {
+            Token::Number(number) => number,
+            Token::Open => {
+                let left = self.magnitude_from(i, rules);
+                let right = self.magnitude_from(i, rules);
+                *i += 1;
+                rules.magnitude_weights.0 * left + rules.magnitude_weights.1 * right
+            },
+            Token::Close => unreachable!(),
+        }
+    }
+
+    pub fn magnitude(&self, rules: &ReductionRules) -> usize {
+        self.magnitude_from(&mut 0, rules)
+    }
+}
+
+impl Display for ReferenceNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, token) in self.tokens.iter().enumerate() {
+            let follows_element = i > 0 && matches!(self.tokens[i - 1], Token::Close | Token::Number(_));
+            if follows_element && matches!(token, Token::Open | Token::Number(_)) {
+                write!(f, ",")?;
+            }
+            match token {
+                Token::Open => write!(f, "[")?,
+                Token::Close => write!(f, "]")?,
+                Token::Number(number) => write!(f, "{}", number)?,
+            }
+        }
+        Ok(())
+    }
+}
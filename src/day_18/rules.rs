@@ -0,0 +1,105 @@
+/// Which half of an odd regular number gets the extra one when it splits.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitRounding {
+    /// The left half rounds down and the right one up, as in the puzzle.
+    LeftDown,
+    LeftUp,
+}
+
+/// Parameters of snailfish arithmetic: pairs nested inside `max_depth` pairs explode, regular numbers of at least
+/// `split_threshold` split, and the magnitude of a pair weighs its left and right halves by `magnitude_weights`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReductionRules {
+    pub max_depth: usize,
+    pub split_threshold: usize,
+    pub split_rounding: SplitRounding,
+    pub magnitude_weights: (usize, usize),
+}
+
+/// The rules from the puzzle.
+impl Default for ReductionRules {
+    fn default() -> Self {
+        ReductionRules { max_depth: 4, split_threshold: 10, split_rounding: SplitRounding::LeftDown, magnitude_weights: (3, 2) }
+    }
+}
+
+#[allow(dead_code)]
+impl ReductionRules {
+    pub fn split_halves(&self, value: usize) -> (usize, usize) {
+        match self.split_rounding {
+            SplitRounding::LeftDown => (value / 2, value.div_ceil(2)),
+            SplitRounding::LeftUp => (value.div_ceil(2), value / 2),
+        }
+    }
+
+    /// Largest magnitude of a reduced number: all of its regular numbers just below the split threshold,
+    /// in pairs nested as deep as they may be without exploding.
+    pub fn max_magnitude(&self) -> usize {
+        let (left_weight, right_weight) = self.magnitude_weights;
+        self.split_threshold.saturating_sub(1) * (left_weight + right_weight).pow(self.max_depth as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_18::SnailfishNumberTree;
+
+    fn reduced(input: &str, rules: &ReductionRules) -> String {
+        let mut number = SnailfishNumberTree::from_input(input);
+        number.reduce_with(rules);
+        number.to_string()
+    }
+
+    #[test]
+    fn puzzle_rules_by_default() {
+        let a = SnailfishNumberTree::from_input("[[[[4,3],4],4],[7,[[8,4],9]]]");
+        let b = SnailfishNumberTree::from_input("[1,1]");
+        assert_eq!(a.clone().add_with(b.clone(), &ReductionRules::default()), a + b);
+    }
+
+    #[test]
+    fn max_depth_works() {
+        let rules = ReductionRules { max_depth: 2, ..ReductionRules::default() };
+        assert_eq!(reduced("[[[1,2],3],4]", &rules), "[[0,5],4]");
+        assert_eq!(reduced("[[1,2],3]", &rules), "[[1,2],3]");
+        assert_eq!(reduced("[[[[9,8],1],2],3]", &ReductionRules::default()), "[[[[9,8],1],2],3]");
+    }
+
+    #[test]
+    fn splits_work() {
+        let rules = ReductionRules { split_threshold: 5, ..ReductionRules::default() };
+        assert_eq!(reduced("[7,1]", &rules), "[[3,4],1]");
+        assert_eq!(reduced("[7,1]", &ReductionRules::default()), "[7,1]");
+
+        let rules = ReductionRules { split_rounding: SplitRounding::LeftUp, ..ReductionRules::default() };
+        assert_eq!(reduced("[11,1]", &rules), "[[6,5],1]");
+        assert_eq!(reduced("[11,1]", &ReductionRules::default()), "[[5,6],1]");
+    }
+
+    #[test]
+    #[should_panic(expected = "Split threshold")]
+    fn zero_split_threshold_is_rejected() {
+        reduced("[0,1]", &ReductionRules { split_threshold: 0, ..ReductionRules::default() });
+    }
+
+    #[test]
+    fn magnitude_weights_work() {
+        let number = SnailfishNumberTree::from_input("[[1,2],[[3,4],5]]");
+        assert_eq!(number.magnitude_with(&ReductionRules { magnitude_weights: (1, 1), ..ReductionRules::default() }), 15);
+        assert_eq!(number.magnitude_with(&ReductionRules { magnitude_weights: (2, 3), ..ReductionRules::default() }), 2 * 8 + 3 * (2 * 18 + 3 * 5));
+        assert_eq!(number.magnitude_with(&ReductionRules::default()), 143);
+    }
+
+    #[test]
+    fn max_magnitude_is_reached() {
+        assert_eq!(ReductionRules::default().max_magnitude(), 5625);
+        let deepest = "[[[[9,9],[9,9]],[[9,9],[9,9]]],[[[9,9],[9,9]],[[9,9],[9,9]]]]";
+        assert_eq!(SnailfishNumberTree::from_input(deepest).magnitude(), 5625);
+
+        let rules = ReductionRules { max_depth: 2, split_threshold: 5, magnitude_weights: (1, 2), ..ReductionRules::default() };
+        assert_eq!(rules.max_magnitude(), 36);
+        assert_eq!(SnailfishNumberTree::from_input("[[4,4],[4,4]]").magnitude_with(&rules), 36);
+    }
+}
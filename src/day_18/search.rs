@@ -0,0 +1,126 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use super::flat::FlatSnailfishNumber;
+use crate::work_queue::fold_parallel;
+
+/// The sum of the numbers at indices `i` and `j`, in that order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairSum {
+    i: usize,
+    j: usize,
+    sum: FlatSnailfishNumber,
+    magnitude: usize,
+}
+
+#[allow(dead_code)]
+impl PairSum {
+    pub fn i(&self) -> usize {
+        self.i
+    }
+
+    pub fn j(&self) -> usize {
+        self.j
+    }
+
+    pub fn sum(&self) -> &FlatSnailfishNumber {
+        &self.sum
+    }
+
+    pub fn magnitude(&self) -> usize {
+        self.magnitude
+    }
+}
+
+/// Larger is better: higher magnitudes first, ties going to the lower indices.
+type Rank = (usize, Reverse<(usize, usize)>);
+
+/// Keeps the `k` best ranks seen so far, the worst of them on top of the heap.
+fn push_bounded(best: &mut BinaryHeap<Reverse<Rank>>, rank: Rank, k: usize) {
+    if best.len() < k {
+        best.push(Reverse(rank));
+    } else if best.peek().is_some_and(|Reverse(worst)| *worst < rank) {
+        best.pop();
+        best.push(Reverse(rank));
+    }
+}
+
+/// The `k` ordered pairs of distinct numbers with the largest magnitude of their sum, best first. The rows of the
+/// pair grid are spread over `num_threads` threads, each keeping its own top `k` so the threads only meet again
+/// when merging them.
+pub fn top_pairs(numbers: &[FlatSnailfishNumber], k: usize, num_threads: usize) -> Vec<PairSum> {
+    let workers = fold_parallel(numbers, num_threads, || BinaryHeap::with_capacity(k + 1), |best, i, a| {
+        for (j, b) in numbers.iter().enumerate() {
+            if i != j {
+                push_bounded(best, ((a + b).magnitude(), Reverse((i, j))), k);
+            }
+        }
+    });
+    let mut best = BinaryHeap::with_capacity(k + 1);
+    for Reverse(rank) in workers.into_iter().flatten() {
+        push_bounded(&mut best, rank, k);
+    }
+
+    let mut ranks: Vec<Rank> = best.into_iter().map(|Reverse(rank)| rank).collect();
+    ranks.sort_unstable_by(|a, b| b.cmp(a));
+    ranks.into_iter()
+        .map(|(magnitude, Reverse((i, j)))| PairSum { i, j, sum: &numbers[i] + &numbers[j], magnitude })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+[[[5,[2,8]],4],[5,[[9,9],0]]]
+[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
+[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
+[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
+[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
+[[[[5,4],[7,7]],8],[[8,3],8]]
+[[9,3],[[9,9],[6,[4,9]]]]
+[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
+[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]";
+
+    fn numbers() -> Vec<FlatSnailfishNumber> {
+        EXAMPLE.lines().map(FlatSnailfishNumber::from_input).collect()
+    }
+
+    #[test]
+    fn aoc_example_works() {
+        let best = &top_pairs(&numbers(), 1, 4)[0];
+        assert_eq!(best.magnitude(), 3_993);
+        assert_eq!((best.i(), best.j()), (8, 0));
+        assert_eq!(best.sum().to_string(), "[[[[7,8],[6,6]],[[6,0],[7,7]]],[[[7,8],[8,8]],[[7,9],[0,6]]]]");
+    }
+
+    #[test]
+    fn top_k_is_ranked_and_independent_of_threads() {
+        let numbers = numbers();
+        let mut all: Vec<(usize, usize, usize)> = Vec::new();
+        for i in 0..numbers.len() {
+            for j in 0..numbers.len() {
+                if i != j {
+                    all.push(((&numbers[i] + &numbers[j]).magnitude(), i, j));
+                }
+            }
+        }
+        all.sort_unstable_by_key(|&(magnitude, i, j)| (Reverse(magnitude), i, j));
+
+        let expected: Vec<(usize, usize, usize)> = all[..7].to_vec();
+        for num_threads in [1, 2, 5, 16] {
+            let top: Vec<(usize, usize, usize)> = top_pairs(&numbers, 7, num_threads).iter().map(|pair| (pair.magnitude(), pair.i(), pair.j())).collect();
+            assert_eq!(top, expected);
+        }
+        assert_eq!(top_pairs(&numbers, 1000, 3).len(), 90);
+        assert!(top_pairs(&numbers, 0, 3).is_empty());
+    }
+
+    #[test]
+    fn self_pairs_are_skipped() {
+        // Adding the first number to itself would beat the sum of the two different numbers
+        let numbers: Vec<FlatSnailfishNumber> = ["[9,9]", "[1,1]"].iter().map(|input| FlatSnailfishNumber::from_input(input)).collect();
+        let top = top_pairs(&numbers, 5, 2);
+        assert_eq!(top.iter().map(|pair| (pair.i(), pair.j())).collect::<Vec<_>>(), [(0, 1), (1, 0)]);
+    }
+}
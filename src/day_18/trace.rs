@@ -0,0 +1,120 @@
+use std::fmt::{Display, Formatter};
+
+use super::{ReductionRules, SnailfishNumberTree};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReductionAction {
+    Addition,
+    /// The pair at `path` exploded. Its values moved to the closest regular numbers to either side, if there were any.
+    Explode { path: Vec<Side>, left: usize, right: usize, left_moved: bool, right_moved: bool },
+    /// The regular number `value` at `path` split into a pair.
+    Split { path: Vec<Side>, value: usize },
+}
+
+/// An action taken while adding snailfish numbers, and the number right after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReductionStep {
+    action: ReductionAction,
+    result: String,
+}
+
+#[allow(dead_code)]
+impl ReductionStep {
+    pub fn from_action(action: ReductionAction, result: &SnailfishNumberTree) -> Self {
+        ReductionStep { action, result: result.to_string() }
+    }
+
+    pub fn action(&self) -> &ReductionAction {
+        &self.action
+    }
+
+    pub fn result(&self) -> &str {
+        &self.result
+    }
+}
+
+/// Every step of an addition, printed like the worked example of the puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReductionTrace {
+    steps: Vec<ReductionStep>,
+}
+
+#[allow(dead_code)]
+impl ReductionTrace {
+    pub fn steps(&self) -> &[ReductionStep] {
+        &self.steps
+    }
+}
+
+impl Display for ReductionTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for step in &self.steps {
+            let label = match step.action {
+                ReductionAction::Addition => "after addition:",
+                ReductionAction::Explode { .. } => "after explode:",
+                ReductionAction::Split { .. } => "after split:",
+            };
+            writeln!(f, "{:<16}{}", label, step.result)?;
+        }
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
+impl SnailfishNumberTree {
+    /// Adds like `+`, but also returns every step taken on the way.
+    pub fn add_traced(self, rhs: SnailfishNumberTree, rules: &ReductionRules) -> (Self, ReductionTrace) {
+        let mut res = self.pair_with(rhs);
+        let mut steps = vec![ReductionStep::from_action(ReductionAction::Addition, &res)];
+        res.reduce_recording(rules, Some(&mut steps));
+        (res, ReductionTrace { steps })
+    }
+
+    pub fn reduce_traced(&mut self, rules: &ReductionRules) -> ReductionTrace {
+        let mut steps = Vec::new();
+        self.reduce_recording(rules, Some(&mut steps));
+        ReductionTrace { steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Side::{Left, Right};
+
+    #[test]
+    fn aoc_example_works() {
+        let a = SnailfishNumberTree::from_input("[[[[4,3],4],4],[7,[[8,4],9]]]");
+        let b = SnailfishNumberTree::from_input("[1,1]");
+        let (sum, trace) = a.add_traced(b, &ReductionRules::default());
+        assert_eq!(sum.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+        assert_eq!(trace.to_string(), "\
+after addition: [[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]
+after explode:  [[[[0,7],4],[7,[[8,4],9]]],[1,1]]
+after explode:  [[[[0,7],4],[15,[0,13]]],[1,1]]
+after split:    [[[[0,7],4],[[7,8],[0,13]]],[1,1]]
+after split:    [[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]
+after explode:  [[[[0,7],4],[[7,8],[6,0]]],[8,1]]
+");
+
+        let actions: Vec<&ReductionAction> = trace.steps().iter().map(ReductionStep::action).collect();
+        assert_eq!(actions[1], &ReductionAction::Explode { path: vec![Left, Left, Left, Left], left: 4, right: 3, left_moved: false, right_moved: true });
+        assert_eq!(actions[2], &ReductionAction::Explode { path: vec![Left, Right, Right, Left], left: 8, right: 4, left_moved: true, right_moved: true });
+        assert_eq!(actions[3], &ReductionAction::Split { path: vec![Left, Right, Left], value: 15 });
+        assert_eq!(actions[4], &ReductionAction::Split { path: vec![Left, Right, Right, Right], value: 13 });
+        assert_eq!(actions[5], &ReductionAction::Explode { path: vec![Left, Right, Right, Right], left: 6, right: 7, left_moved: true, right_moved: true });
+    }
+
+    #[test]
+    fn reduced_numbers_have_empty_traces() {
+        let mut a = SnailfishNumberTree::from_input("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+        assert!(a.reduce_traced(&ReductionRules::default()).steps().is_empty());
+    }
+}
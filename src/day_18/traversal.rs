@@ -0,0 +1,223 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+
+use super::trace::Side;
+use super::SnailfishNumberNode;
+
+/// Nodes at most this high are small enough to clone and drop recursively, which is faster.
+const MAX_RECURSION_HEIGHT: usize = 64;
+
+/// A snailfish number written out without commas, the order its nodes are visited in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Token {
+    Open,
+    Regular(usize),
+    Close,
+}
+
+enum Visit<'a> {
+    Enter(&'a SnailfishNumberNode),
+    Leave,
+}
+
+/// Walks the tokens of a node with an explicit stack.
+pub struct Tokens<'a> {
+    stack: Vec<Visit<'a>>,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.stack.pop()? {
+            Visit::Enter(node) => {
+                if let Some(value) = node.regular_number_value {
+                    return Some(Token::Regular(value))
+                }
+                self.stack.push(Visit::Leave);
+                self.stack.push(Visit::Enter(node.right.as_ref().unwrap()));
+                self.stack.push(Visit::Enter(node.left.as_ref().unwrap()));
+                Some(Token::Open)
+            },
+            Visit::Leave => Some(Token::Close),
+        }
+    }
+}
+
+impl SnailfishNumberNode {
+    pub fn tokens(&self) -> Tokens<'_> {
+        Tokens { stack: vec![Visit::Enter(self)] }
+    }
+
+    /// Builds the node back from its tokens, which have to be well-formed.
+    pub fn from_tokens(tokens: impl IntoIterator<Item = Token>) -> Box<Self> {
+        let mut finished: Vec<Box<SnailfishNumberNode>> = Vec::new();
+        for token in tokens {
+            match token {
+                Token::Open => {},
+                Token::Regular(value) => finished.push(Box::new(SnailfishNumberNode::leaf_from_regular_value(value))),
+                Token::Close => {
+                    let right = finished.pop().unwrap();
+                    let left = finished.pop().unwrap();
+                    finished.push(Box::new(SnailfishNumberNode::node_with_children(left, right)));
+                },
+            }
+        }
+        assert_eq!(finished.len(), 1, "Tokens have to form exactly one snailfish number");
+        finished.pop().unwrap()
+    }
+
+    /// Path to the first node in pre-order for which `is_target` holds.
+    pub fn find_path(&self, is_target: impl Fn(&Self) -> bool) -> Option<Vec<Side>> {
+        let mut path = Vec::with_capacity(self.height);
+        let mut stack = Vec::with_capacity(self.height + 1);
+        stack.push((self, 0_usize, None));
+        while let Some((node, depth, side)) = stack.pop() {
+            path.truncate(depth.saturating_sub(1));
+            path.extend(side);
+            if is_target(node) {
+                return Some(path)
+            }
+            if node.regular_number_value.is_none() {
+                stack.push((node.right.as_ref().unwrap(), depth + 1, Some(Side::Right)));
+                stack.push((node.left.as_ref().unwrap(), depth + 1, Some(Side::Left)));
+            }
+        }
+        None
+    }
+}
+
+/// Drops the children of high nodes one at a time instead of recursively.
+impl Drop for SnailfishNumberNode {
+    fn drop(&mut self) {
+        if self.height <= MAX_RECURSION_HEIGHT {
+            return
+        }
+        let mut children: Vec<Box<SnailfishNumberNode>> = self.left.take().into_iter().chain(self.right.take()).collect();
+        while let Some(mut child) = children.pop() {
+            children.extend(child.left.take());
+            children.extend(child.right.take());
+        }
+    }
+}
+
+impl Clone for SnailfishNumberNode {
+    fn clone(&self) -> Self {
+        if self.height > MAX_RECURSION_HEIGHT {
+            return *SnailfishNumberNode::from_tokens(self.tokens())
+        }
+        SnailfishNumberNode {
+            regular_number_value: self.regular_number_value,
+            left: self.left.clone(),
+            right: self.right.clone(),
+            height: self.height,
+        }
+    }
+
+    /// Copies `source` into the nodes `self` already has wherever both are pairs, allocating only the rest.
+    fn clone_from(&mut self, source: &Self) {
+        if self.height > MAX_RECURSION_HEIGHT || source.height > MAX_RECURSION_HEIGHT {
+            *self = source.clone();
+            return
+        }
+        self.regular_number_value = source.regular_number_value;
+        self.height = source.height;
+        self.left.clone_from(&source.left);
+        self.right.clone_from(&source.right);
+    }
+}
+
+impl PartialEq for SnailfishNumberNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.tokens().eq(other.tokens())
+    }
+}
+
+impl Eq for SnailfishNumberNode {}
+
+impl Hash for SnailfishNumberNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tokens().for_each(|token| token.hash(state));
+    }
+}
+
+impl Ord for SnailfishNumberNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.tokens().cmp(other.tokens())
+    }
+}
+
+impl PartialOrd for SnailfishNumberNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Debug for SnailfishNumberNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::day_18::reference::ReferenceNumber;
+    use crate::day_18::rules::ReductionRules;
+    use crate::day_18::SnailfishNumberTree;
+
+    /// `[[...[[first,1],1]...,1],1]` with `depth` pairs.
+    fn deeply_nested(depth: usize, first: usize) -> String {
+        format!("{}{},1]{}", "[".repeat(depth), first, ",1]".repeat(depth - 1))
+    }
+
+    #[test]
+    fn deep_numbers_do_not_overflow_the_stack() {
+        let input = deeply_nested(200_000, 1);
+        let number = SnailfishNumberTree::from_input(&input);
+        assert_eq!(number.to_string(), input);
+        assert_eq!(number.magnitude_with(&ReductionRules { magnitude_weights: (1, 1), ..ReductionRules::default() }), 200_001);
+
+        let copy = number.clone();
+        assert_eq!(copy, number);
+        let other = SnailfishNumberTree::from_input(&deeply_nested(200_000, 2));
+        assert_ne!(other, number);
+        assert_eq!(HashSet::from([copy, number, other]).len(), 2);
+
+        let unclosed = "[".repeat(200_000);
+        assert!(unclosed.parse::<SnailfishNumberTree>().is_err());
+    }
+
+    #[test]
+    fn deep_magnitudes_saturate() {
+        let deep = SnailfishNumberTree::from_input(&deeply_nested(100, 1));
+        assert_eq!(deep.magnitude(), usize::MAX);
+        let shallow = SnailfishNumberTree::from_input(&deeply_nested(3, 1));
+        assert_eq!(shallow.magnitude(), 53);
+        assert!(shallow < deep);
+
+        let mut numbers = [deep.clone(), shallow.clone(), SnailfishNumberTree::from_input(&deeply_nested(100, 2))];
+        numbers.sort();
+        assert_eq!(numbers[..2], [shallow, deep]);
+    }
+
+    #[test]
+    fn deep_numbers_reduce_like_the_reference() {
+        let input = format!("{}0,0]{}", "[".repeat(2_000), ",0]".repeat(1_999));
+        let mut number = SnailfishNumberTree::from_input(&input);
+        number.reduce();
+        let mut reference = ReferenceNumber::from_input(&input);
+        reference.reduce(&ReductionRules::default());
+        assert_eq!(number.to_string(), reference.to_string());
+        assert_eq!(number.to_string(), "[[[[0,0],0],0],0]");
+
+        let input = deeply_nested(300, 1);
+        let mut number = SnailfishNumberTree::from_input(&input);
+        number.reduce();
+        let mut reference = ReferenceNumber::from_input(&input);
+        reference.reduce(&ReductionRules::default());
+        assert_eq!(number.to_string(), reference.to_string());
+    }
+}
@@ -6,16 +6,55 @@ mod day_17;
 mod day_18;
 
 mod array_2d;
+#[cfg(test)]
+mod rng;
+mod work_queue;
 
 
-use crate::benchmark::{benchmark_run, print_day, print_header};
+use crate::benchmark::{benchmark_run, print_day, print_header, print_variant, print_variants_header, read_day_input};
 extern crate lazy_static;
 
+use std::hint::black_box;
+
+/// Evaluations of a compiled transmission timed together, since a single one is too short to time.
+const EVALUATIONS: usize = 1_000;
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mode: Option<Result<(), Box<dyn std::error::Error>>> = match args.first().map(String::as_str) {
+        Some("snailfish-homework") => Some(day_18::print_homework(&args[1..]).map_err(Into::into)),
+        Some("trick-shot-sweep") => Some(day_17::print_sweep(&args[1..]).map_err(Into::into)),
+        _ => None,
+    };
+    if let Some(result) = mode {
+        if let Err(error) = result {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        return
+    }
+
     benchmark_all!(
         day_15,
         day_16,
         day_17,
         day_18
     );
+    benchmark_variants!(
+        day_17::part_2_analytic,
+        day_17::part_2_parallel,
+        day_17::part_2_naive,
+        day_18::part_1_flat,
+        day_18::part_2_flat,
+        day_18::part_1_arena,
+        day_18::part_2_arena,
+        day_18::part_2_parallel
+    );
+
+    // Decoding and compiling would dominate a single evaluation, so both variants share one compiled transmission
+    let transmission = day_16::compile_transmission(&read_day_input("16"));
+    for (variant, evaluate) in [("part_2_tree", day_16::part_2_tree as fn(&_) -> u128), ("part_2_vm", day_16::part_2_vm)] {
+        let duration = benchmark_run(|transmission| (0..EVALUATIONS).fold(0, |_, _| evaluate(black_box(transmission))), &transmission);
+        print_variant(16, &format!("{} x{}", variant, EVALUATIONS), duration);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,31 @@
+/// Small xorshift generator for randomised tests, seeded explicitly so failures can be replayed.
+pub struct XorShift64 {
+    state: u64,
+}
+
+#[allow(dead_code)]
+impl XorShift64 {
+    pub fn from_seed(seed: u64) -> Self {
+        XorShift64 { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Uniform-ish value in `low..=high`.
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + (self.next_u64() % ((high - low) as u64 + 1)) as i64
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.next_u64() % denominator < numerator
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Folds `items` on `num_threads` scoped threads. The items are handed out one at a time, so a thread that drew
+/// cheap items just takes more of them. Each thread folds its share into its own state starting from `init()`,
+/// and the states are returned for the caller to merge.
+pub fn fold_parallel<T, S, I, F>(items: &[T], num_threads: usize, init: I, fold: F) -> Vec<S>
+where
+    T: Sync,
+    S: Send,
+    I: Fn() -> S + Sync,
+    F: Fn(&mut S, usize, &T) + Sync,
+{
+    let next_item = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..num_threads.max(1)).map(|_| scope.spawn(|| {
+            let mut state = init();
+            loop {
+                let index = next_item.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else { break };
+                fold(&mut state, index, item);
+            }
+            state
+        })).collect();
+        workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_item_is_folded_once() {
+        let items: Vec<usize> = (0..1_000).collect();
+        for num_threads in [0, 1, 3, 16] {
+            let states = fold_parallel(&items, num_threads, Vec::new, |seen, index, &item| {
+                assert_eq!(index, item);
+                seen.push(item);
+            });
+            assert_eq!(states.len(), num_threads.max(1));
+            let mut seen: Vec<usize> = states.into_iter().flatten().collect();
+            seen.sort_unstable();
+            assert_eq!(seen, items);
+        }
+    }
+}